@@ -0,0 +1,48 @@
+use rand::random;
+
+use obliviousdb::cache_sim::{block_transfers_per_query, SortedArray, BTreeModel, BlockTransfers};
+use obliviousdb::search_tree::{SearchTree, HybridSearchTree, CACHE_LINE_BLOCK_HEIGHT};
+
+// Not a timing benchmark: prints the block transfers per query of each layout
+// under the ideal-cache model, for a sweep of block sizes B and a fixed cache size M.
+fn print_report(name: &str, report: &[BlockTransfers], count: usize) {
+    println!("{}", name);
+    for BlockTransfers { block_size, transfers_per_query } in report {
+        let log_b_n = if *block_size > 1 { (count as f64).log(*block_size as f64) } else { f64::NAN };
+        println!("    B = {:>5}   transfers/query = {:>7.2}   log_B(n) = {:>6.2}",
+                 block_size, transfers_per_query, log_b_n);
+    }
+}
+
+fn main() {
+    let count: usize = 1 << 20;
+    let cache_size = 1 << 14;
+    let block_sizes: Vec<usize> = (0..11).map(|i| 1 << i).collect();
+    let queries: Vec<i32> = (0..10_000).map(|_| random::<i32>().rem_euclid(count as i32)).collect();
+
+    println!("n = {}, M = {} elements, {} random queries", count, cache_size, queries.len());
+
+    let search_tree = SearchTree::new(0..count as i32, count).unwrap();
+    print_report("cache-oblivious search",
+        &block_transfers_per_query(&block_sizes, cache_size, &queries,
+            |query, cache| { search_tree.search_with_probe(query, cache); }),
+        count);
+
+    let hybrid = HybridSearchTree::new(0..count as i32, count, CACHE_LINE_BLOCK_HEIGHT).unwrap();
+    print_report("hybrid cache-oblivious search, blocks of 16 leaves",
+        &block_transfers_per_query(&block_sizes, cache_size, &queries,
+            |query, cache| { hybrid.search_with_probe(query, cache); }),
+        count);
+
+    let sorted_array = SortedArray::new(0..count as i32);
+    print_report("sorted array binary search",
+        &block_transfers_per_query(&block_sizes, cache_size, &queries,
+            |query, cache| { sorted_array.search_with_probe(query, cache); }),
+        count);
+
+    let btree = BTreeModel::new(0..count as i32);
+    print_report("btreemap model",
+        &block_transfers_per_query(&block_sizes, cache_size, &queries,
+            |query, cache| { btree.search_with_probe(query, cache); }),
+        count);
+}
@@ -1,9 +1,10 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
 use rand::random;
 
 mod perf;
 
-use obliviousdb::search_tree::SearchTree;
+use obliviousdb::search_tree::{SearchTree, HybridSearchTree};
+use obliviousdb::static_index::{StaticIndex, Eytzinger, STree, SortedArray};
 
 fn benchmark_search_oblivious_static_search_tree(c: &mut Criterion) {
     let max: i32 = 268_435_456;
@@ -40,6 +41,33 @@ fn benchmark_search_std_collection_btreemap(c: &mut Criterion) {
 }
 
 
+// Every layout is built from the same keys and queried with the same stream of random keys
+fn benchmark_static_index<I: StaticIndex>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group("static index lower bound");
+
+    for count in [1 << 16, 1 << 20, 1 << 24].iter() {
+        let keys: Vec<i32> = (0..*count).map(|i| i*2).collect();
+        let index = I::build(&keys).unwrap();
+        let queries: Vec<i32> = (0..4096).map(|_| random::<i32>().rem_euclid(2 * *count)).collect();
+
+        println!("{} with {} keys: {} bytes", name, count, index.size_in_bytes());
+        group.bench_with_input(BenchmarkId::new(name, count), &queries,
+        |b, queries| {
+            let mut i = queries.iter().cycle();
+            b.iter(|| index.lower_bound(*i.next().unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn benchmark_static_indexes(c: &mut Criterion) {
+    benchmark_static_index::<SearchTree>(c, "van emde boas");
+    benchmark_static_index::<HybridSearchTree>(c, "hybrid van emde boas");
+    benchmark_static_index::<Eytzinger>(c, "eytzinger");
+    benchmark_static_index::<STree>(c, "s-tree");
+    benchmark_static_index::<SortedArray>(c, "sorted array");
+}
+
 /*
 
 criterion_group!{
@@ -49,6 +77,7 @@ criterion_group!{
     targets = benchmark_search_oblivious_static_search_tree, benchmark_search_std_collection_btreemap
 }
 */
-criterion_group!(benches,benchmark_search_std_collection_btreemap,benchmark_search_oblivious_static_search_tree);
+criterion_group!(benches,benchmark_search_std_collection_btreemap,benchmark_search_oblivious_static_search_tree,
+                 benchmark_static_indexes);
 
 criterion_main!(benches);
@@ -0,0 +1,145 @@
+use crate::search_tree::Probe;
+
+// Sorted array searched with a textbook binary search. Returns the position of the greatest
+// element less than or equal to the searched one, like SearchTree::search.
+pub struct SortedArray {
+    array: Box<[i32]>,
+}
+
+impl SortedArray {
+    pub fn new(generator: impl Iterator<Item=i32>) -> SortedArray {
+        SortedArray { array: generator.collect::<Vec<i32>>().into_boxed_slice() }
+    }
+
+    pub fn search_with_probe<P: Probe>(&self, element: i32, probe: &mut P) -> Option<usize> {
+        let (mut low, mut high) = (0, self.array.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            probe.touch(middle);
+            if self.array[middle] <= element {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low.checked_sub(1)
+    }
+}
+
+// Number of keys in a node of std::collections::BTreeMap (B = 6)
+const CAPACITY: usize = 11;
+// A node is modelled as its keys followed by its child pointers, each pointer taking two i32 slots
+const NODE_SIZE: usize = CAPACITY + 2 * (CAPACITY + 1);
+
+struct Node {
+    keys: Vec<i32>,
+    children: Vec<usize>,
+}
+
+// std's BTreeMap can't be instrumented, so this models its search: nodes of up to 11 keys
+// scanned linearly, then one child pointer followed. Nodes are laid out one after the other in
+// allocation order, which is the best case for the real allocator.
+pub struct BTreeModel {
+    nodes: Vec<Node>,
+}
+
+impl BTreeModel {
+    pub fn new(generator: impl Iterator<Item=i32>) -> BTreeModel {
+        let keys: Vec<i32> = generator.collect();
+
+        let mut height = 1;
+        while capacity_of_tree_with_height(height) < keys.len() {
+            height += 1;
+        }
+
+        let mut model = BTreeModel { nodes: Vec::new() };
+        model.bulk_load(&keys, height);
+        model
+    }
+
+    fn bulk_load(&mut self, keys: &[i32], height: u32) -> usize {
+        let node_number = self.nodes.len();
+        self.nodes.push(Node { keys: Vec::new(), children: Vec::new() });
+
+        if height == 1 {
+            self.nodes[node_number].keys = keys.to_vec();
+            return node_number;
+        }
+
+        let child_capacity = capacity_of_tree_with_height(height - 1);
+        let number_of_children = ((keys.len() + 1 + child_capacity) / (child_capacity + 1)).max(2);
+        let keys_in_children = keys.len() - (number_of_children - 1);
+
+        let mut start = 0;
+        for child in 0..number_of_children {
+            let size = keys_in_children / number_of_children
+                + (child < keys_in_children % number_of_children) as usize;
+            let child_node = self.bulk_load(&keys[start..start + size], height - 1);
+            self.nodes[node_number].children.push(child_node);
+
+            if child + 1 < number_of_children {
+                self.nodes[node_number].keys.push(keys[start + size]);
+            }
+            start += size + 1;
+        }
+        node_number
+    }
+
+    pub fn search_with_probe<P: Probe>(&self, element: i32, probe: &mut P) -> bool {
+        let mut node_number = 0;
+        loop {
+            let node = &self.nodes[node_number];
+            let base = node_number * NODE_SIZE;
+
+            let mut edge = node.keys.len();
+            for (i, &key) in node.keys.iter().enumerate() {
+                probe.touch(base + i);
+                if key == element {
+                    return true;
+                }
+                if element < key {
+                    edge = i;
+                    break;
+                }
+            }
+
+            if node.children.is_empty() {
+                return false;
+            }
+            probe.touch(base + CAPACITY + 2 * edge);
+            node_number = node.children[edge];
+        }
+    }
+}
+
+fn capacity_of_tree_with_height(height: u32) -> usize {
+    (CAPACITY + 1).pow(height) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache_sim::baselines::{SortedArray, BTreeModel};
+    use crate::search_tree::NoProbe;
+
+    #[test]
+    fn sorted_array_finds_lower_bound() {
+        let array = SortedArray::new((0..100).map(|i| i*2));
+
+        assert_eq!(array.search_with_probe(10, &mut NoProbe), Some(5), "Element in array");
+        assert_eq!(array.search_with_probe(11, &mut NoProbe), Some(5), "Element not in array");
+        assert_eq!(array.search_with_probe(-1, &mut NoProbe), None, "Element smaller than array");
+        assert_eq!(array.search_with_probe(500, &mut NoProbe), Some(99), "Element greater than array");
+    }
+
+    #[test]
+    fn btree_model_finds_every_key() {
+        for count in &[0, 1, 11, 12, 143, 144, 1000] {
+            let model = BTreeModel::new((0..*count).map(|i| i*2));
+
+            for key in 0..*count {
+                assert!(model.search_with_probe(key*2, &mut NoProbe), "{} in tree of {}", key*2, count);
+                assert!(!model.search_with_probe(key*2 + 1, &mut NoProbe), "{} not in tree of {}", key*2+1, count);
+            }
+        }
+    }
+}
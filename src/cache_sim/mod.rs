@@ -0,0 +1,163 @@
+mod baselines;
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::search_tree::Probe;
+
+pub use baselines::{SortedArray, BTreeModel};
+
+// Ideal-cache model: a fully associative cache of `cache_size` elements split into blocks of
+// `block_size` elements, with least-recently-used replacement. Addresses are array indices, so
+// sizes are counted in elements (i32) rather than bytes.
+pub struct CacheSimulator {
+    block_size: usize,
+    capacity_in_blocks: usize,
+    last_used: HashMap<usize, u64>,
+    by_age: BTreeMap<u64, usize>,
+    clock: u64,
+    transfers: u64,
+}
+
+impl CacheSimulator {
+    pub fn new(cache_size: usize, block_size: usize) -> CacheSimulator {
+        assert!(block_size > 0 && cache_size >= block_size,
+                "Cache must hold at least one block. M: {}, B: {}", cache_size, block_size);
+
+        CacheSimulator {
+            block_size,
+            capacity_in_blocks: cache_size / block_size,
+            last_used: HashMap::new(),
+            by_age: BTreeMap::new(),
+            clock: 0,
+            transfers: 0,
+        }
+    }
+
+    pub fn access(&mut self, address: usize) {
+        let block = address / self.block_size;
+        self.clock += 1;
+
+        if let Some(age) = self.last_used.insert(block, self.clock) {
+            self.by_age.remove(&age);
+        } else {
+            self.transfers += 1;
+            if self.last_used.len() > self.capacity_in_blocks {
+                let (_, evicted) = self.by_age.pop_first().unwrap();
+                self.last_used.remove(&evicted);
+            }
+        }
+        self.by_age.insert(self.clock, block);
+    }
+
+    pub fn transfers(&self) -> u64 {
+        self.transfers
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+}
+
+impl Probe for CacheSimulator {
+    fn touch(&mut self, index: usize) {
+        self.access(index)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BlockTransfers {
+    pub block_size: usize,
+    pub transfers_per_query: f64,
+}
+
+// Runs every query against a cold cache of `cache_size` elements once per block size.
+// The cache stays warm between the queries of one block size, as it would in a real query stream.
+pub fn block_transfers_per_query<F>(
+    block_sizes: &[usize],
+    cache_size: usize,
+    queries: &[i32],
+    mut run_query: F
+) -> Vec<BlockTransfers> where F: FnMut(i32, &mut CacheSimulator) {
+    block_sizes.iter().map(|&block_size| {
+        let mut cache = CacheSimulator::new(cache_size, block_size);
+        queries.iter().for_each(|&query| run_query(query, &mut cache));
+
+        BlockTransfers {
+            block_size,
+            transfers_per_query: cache.transfers() as f64 / queries.len().max(1) as f64
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache_sim::{CacheSimulator, block_transfers_per_query};
+    use crate::search_tree::SearchTree;
+
+    #[test]
+    fn accesses_within_a_block_cost_one_transfer() {
+        let mut cache = CacheSimulator::new(16, 4);
+
+        (0..4).for_each(|address| cache.access(address));
+        assert_eq!(cache.transfers(), 1, "One block holds addresses 0 to 3");
+
+        cache.access(4);
+        assert_eq!(cache.transfers(), 2, "Address 4 starts the next block");
+    }
+
+    #[test]
+    fn least_recently_used_block_is_evicted() {
+        let mut cache = CacheSimulator::new(2, 1);
+
+        cache.access(0);
+        cache.access(1);
+        cache.access(0);
+        cache.access(2);
+        assert_eq!(cache.transfers(), 3);
+
+        cache.access(0);
+        assert_eq!(cache.transfers(), 3, "Block 0 was used more recently than block 1");
+
+        cache.access(1);
+        assert_eq!(cache.transfers(), 4, "Block 1 was evicted by block 2");
+    }
+
+    #[test]
+    fn probed_search_matches_search() {
+        let leaves : Vec<i32> = (0..256).map(|i| i*3).collect();
+        let search_tree = SearchTree::new(leaves.into_iter(), 256).unwrap();
+
+        for element in (-5..800).step_by(7) {
+            let mut cache = CacheSimulator::new(64, 8);
+            assert_eq!(search_tree.search_with_probe(element, &mut cache), search_tree.search(element));
+        }
+    }
+
+    #[test]
+    fn whole_tree_in_one_block_costs_one_transfer() {
+        let leaves : Vec<i32> = (0..64).collect();
+        let search_tree = SearchTree::new(leaves.into_iter(), 64).unwrap();
+
+        let report = block_transfers_per_query(&[1, 128], 128, &[0, 17, 63],
+            |query, cache| { search_tree.search_with_probe(query, cache); });
+
+        assert!(report[0].transfers_per_query > 1.0, "A block of one node can't hold the search path");
+        assert_eq!(report[1].transfers_per_query, 1.0 / 3.0,
+                   "Only the first query misses when the whole tree fits in one block");
+    }
+
+    #[test]
+    fn larger_blocks_need_fewer_transfers_for_veb_layout() {
+        let count = 1 << 14;
+        let search_tree = SearchTree::new(0..count, count as usize).unwrap();
+        let queries : Vec<i32> = (0..count).step_by(97).collect();
+
+        let report = block_transfers_per_query(&[1, 4, 16, 64], 256, &queries,
+            |query, cache| { search_tree.search_with_probe(query, cache); });
+
+        report.windows(2).for_each(|pair| assert!(
+            pair[1].transfers_per_query < pair[0].transfers_per_query,
+            "B={} should transfer less than B={}", pair[1].block_size, pair[0].block_size
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::pma::{PackedMemoryArray, Rewritten};
+use crate::search_tree::{SearchTree, SearchTreeIndex};
+
+// Dynamic ordered set after Bender, Demaine and Farach-Colton: the keys live in a packed memory
+// array, and a static vEB search tree over the minimum of every segment finds the segment of a key.
+// Updates rewrite a window of segments and only the index leaves of that window, so both the
+// search and the amortized update cost O(log_B n) block transfers.
+pub struct CacheObliviousBTree {
+    leaves: PackedMemoryArray<i32>,
+    index: SearchTree,
+    //For every segment, the last non-empty segment at or before it, whose minimum is the segment's index key
+    last_non_empty: Vec<Option<usize>>
+}
+
+impl CacheObliviousBTree {
+    pub fn new() -> CacheObliviousBTree {
+        let leaves = PackedMemoryArray::new();
+        let (index, last_non_empty) = CacheObliviousBTree::build_index(&leaves);
+        CacheObliviousBTree { leaves, index, last_non_empty }
+    }
+
+    // An empty segment takes the key of the last non-empty segment before it, or i32::MIN before the first key,
+    // so the keys of the index stay sorted
+    fn key_of_segment(leaves: &PackedMemoryArray<i32>, owner: Option<usize>) -> i32 {
+        owner.map_or(i32::MIN, |owner| leaves.segment(owner)[0])
+    }
+
+    fn build_index(leaves: &PackedMemoryArray<i32>) -> (SearchTree, Vec<Option<usize>>) {
+        let number_of_segments = leaves.number_of_segments();
+        let last_non_empty: Vec<Option<usize>> = (0..number_of_segments).scan(None, |owner, segment| {
+            if !leaves.segment(segment).is_empty() {
+                *owner = Some(segment);
+            }
+            Some(*owner)
+        }).collect();
+
+        let keys = last_non_empty.iter().map(|&owner| CacheObliviousBTree::key_of_segment(leaves, owner));
+        (SearchTree::new(keys, number_of_segments).unwrap(), last_non_empty)
+    }
+
+    fn update_index(&mut self, rewritten: Rewritten) {
+        match rewritten {
+            Rewritten::All => {
+                let (index, last_non_empty) = CacheObliviousBTree::build_index(&self.leaves);
+                self.index = index;
+                self.last_non_empty = last_non_empty;
+            }
+            Rewritten::Segments(segments) => {
+                let mut owner = segments.start.checked_sub(1).and_then(|segment| self.last_non_empty[segment]);
+                //Empty segments after the window inherit from its last segment
+                let end = (segments.end..self.leaves.number_of_segments())
+                    .find(|&segment| !self.leaves.segment(segment).is_empty())
+                    .unwrap_or_else(|| self.leaves.number_of_segments());
+
+                for segment in segments.start..end {
+                    if !self.leaves.segment(segment).is_empty() {
+                        owner = Some(segment);
+                    }
+                    self.last_non_empty[segment] = owner;
+                    self.index.update_leaf(segment, CacheObliviousBTree::key_of_segment(&self.leaves, owner));
+                }
+            }
+        }
+    }
+
+    // Segment holding the greatest key less than or equal to `key`. The index finds the last segment whose
+    // key is at most `key`, and an empty one hands the search to the segment its key came from.
+    fn segment_of_lower_bound(&self, key: i32) -> Option<usize> {
+        match self.index.search(key) {
+            SearchTreeIndex::NotInTree => None,
+            SearchTreeIndex::Leaf { leaf_number, .. } => self.last_non_empty[leaf_number as usize]
+        }
+    }
+
+    pub fn lower_bound(&self, key: i32) -> Option<i32> {
+        self.segment_of_lower_bound(key).map(|segment| {
+            let keys = self.leaves.segment(segment);
+            keys[keys.partition_point(|&k| k <= key) - 1]
+        })
+    }
+
+    pub fn contains(&self, key: i32) -> bool {
+        self.lower_bound(key) == Some(key)
+    }
+
+    pub fn insert(&mut self, key: i32) -> bool {
+        if self.contains(key) {
+            return false
+        }
+
+        let segment = self.segment_of_lower_bound(key).unwrap_or(0);
+        let rewritten = self.leaves.insert_into_segment(segment, key);
+        self.update_index(rewritten);
+        true
+    }
+
+    pub fn remove(&mut self, key: i32) -> bool {
+        let removed = self.segment_of_lower_bound(key)
+            .and_then(|segment| self.leaves.remove_from_segment(segment, &key));
+
+        match removed {
+            Some(rewritten) => { self.update_index(rewritten); true }
+            None => false
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=i32> + '_ {
+        self.leaves.iter().copied()
+    }
+}
+
+impl Default for CacheObliviousBTree {
+    fn default() -> Self {
+        CacheObliviousBTree::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::cob_tree::CacheObliviousBTree;
+
+    #[test]
+    fn insert_and_find_lower_bounds() {
+        let mut tree = CacheObliviousBTree::new();
+        assert_eq!(tree.lower_bound(10), None, "Empty tree");
+
+        for key in (0..1000).rev().map(|i| i*3) {
+            assert!(tree.insert(key));
+        }
+        assert!(!tree.insert(300), "Key is already in the set");
+
+        assert_eq!(tree.len(), 1000);
+        assert_eq!(tree.lower_bound(-1), None, "Key smaller than the set");
+        assert_eq!(tree.lower_bound(301), Some(300), "Key not in the set");
+        assert_eq!(tree.lower_bound(5000), Some(2997), "Key greater than the set");
+        assert!(tree.iter().eq((0..1000).map(|i| i*3)));
+    }
+
+    #[test]
+    fn remove_keys() {
+        let mut tree = CacheObliviousBTree::new();
+        (0..500).for_each(|key| { tree.insert(key); });
+
+        assert!(tree.remove(250));
+        assert!(!tree.remove(250), "Key was already removed");
+        assert_eq!(tree.lower_bound(250), Some(249));
+
+        (0..500).for_each(|key| { tree.remove(key); });
+        assert!(tree.is_empty());
+        assert_eq!(tree.lower_bound(499), None);
+    }
+
+    #[test]
+    fn lower_bounds_after_removing_most_keys() {
+        let mut tree = CacheObliviousBTree::new();
+        (0..4000).for_each(|key| { tree.insert(key); });
+        (0..4000).filter(|key| key % 1000 != 0).for_each(|key| { tree.remove(key); });
+
+        assert!(tree.iter().eq(vec![0, 1000, 2000, 3000]));
+        for key in 0..5000 {
+            assert_eq!(tree.lower_bound(key), Some((key / 1000 * 1000).min(3000)), "Lower bound of {}", key);
+        }
+    }
+
+    #[test]
+    fn random_operations_match_btreeset() {
+        let mut rng = StdRng::seed_from_u64(29);
+        let mut tree = CacheObliviousBTree::new();
+        let mut expected = BTreeSet::new();
+
+        for _ in 0..20_000 {
+            let key = rng.gen_range(-2000..2000);
+            if rng.gen_bool(0.6) {
+                assert_eq!(tree.insert(key), expected.insert(key), "Inserting {}", key);
+            } else {
+                assert_eq!(tree.remove(key), expected.remove(&key), "Removing {}", key);
+            }
+
+            let probe = rng.gen_range(-2100..2100);
+            assert_eq!(tree.lower_bound(probe), expected.range(..=probe).next_back().copied(),
+                       "Lower bound of {}", probe);
+        }
+
+        assert!(tree.iter().eq(expected.iter().copied()));
+    }
+}
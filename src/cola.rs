@@ -0,0 +1,246 @@
+use crate::search_tree::{SearchTree, SearchTreeIndex};
+
+// Every LOOKAHEAD_STRIDE-th entry of a level is copied into the level below it
+const LOOKAHEAD_STRIDE: usize = 8;
+
+#[derive(Clone, Copy)]
+struct Entry {
+    key: i32,
+    is_lookahead: bool,
+    // Greatest key of the level at or before this entry
+    lower_bound: Option<i32>,
+    // Position in the next non-empty level of the last lookahead at or before this entry, 0 if there is none
+    window_start: usize,
+}
+
+// Keys of a level, merged with a sample of the entries of the next non-empty level
+#[derive(Default)]
+struct Level {
+    entries: Vec<Entry>,
+    len: usize,
+}
+
+impl Level {
+    fn build(keys: Vec<i32>, next: Option<&Level>) -> Level {
+        let len = keys.len();
+        let lookaheads: Vec<(i32, usize)> = next.map_or_else(Vec::new, |next| {
+            next.entries.iter().enumerate().step_by(LOOKAHEAD_STRIDE).map(|(position, entry)| (entry.key, position)).collect()
+        });
+
+        let mut entries = Vec::with_capacity(len + lookaheads.len());
+        let mut lower_bound = None;
+        let mut window_start = 0;
+        let mut keys = keys.into_iter().peekable();
+        let mut lookaheads = lookaheads.into_iter().peekable();
+        loop {
+            let take_lookahead = match (keys.peek(), lookaheads.peek()) {
+                (None, None) => break,
+                (Some(key), Some((lookahead, _))) => lookahead <= key,
+                (None, Some(_)) => true,
+                (Some(_), None) => false,
+            };
+
+            let key = if take_lookahead {
+                let (key, position) = lookaheads.next().unwrap();
+                window_start = position;
+                key
+            } else {
+                let key = keys.next().unwrap();
+                lower_bound = Some(key);
+                key
+            };
+            entries.push(Entry { key, is_lookahead: take_lookahead, lower_bound, window_start });
+        }
+
+        Level { entries, len }
+    }
+
+    fn keys(&self) -> impl Iterator<Item=i32> + '_ {
+        self.entries.iter().filter(|entry| !entry.is_lookahead).map(|entry| entry.key)
+    }
+}
+
+fn merge(a: Vec<i32>, b: Vec<i32>) -> Vec<i32> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+    while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+        merged.push(if x <= y { a.next() } else { b.next() }.unwrap());
+    }
+    merged.extend(a);
+    merged.extend(b);
+    merged
+}
+
+// Cache-oblivious lookahead array after Bender et al.: level k is either empty or holds 2^k sorted keys.
+// An insert merges the full levels below the first empty one into it, like incrementing a binary counter,
+// which costs O(log n / B) amortized block transfers. Each level also holds every LOOKAHEAD_STRIDE-th entry
+// of the next non-empty level, so a search scans a window of LOOKAHEAD_STRIDE entries per level after the first.
+// Equal keys are kept.
+pub struct Cola {
+    levels: Vec<Level>,
+    len: usize,
+}
+
+impl Cola {
+    pub fn new() -> Cola {
+        Cola { levels: Vec::new(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, key: i32) {
+        let mut keys = vec![key];
+        let mut level = 0;
+        while level < self.levels.len() && self.levels[level].len > 0 {
+            keys = merge(keys, self.levels[level].keys().collect());
+            self.levels[level] = Level::default();
+            level += 1;
+        }
+
+        if level == self.levels.len() {
+            self.levels.push(Level::default());
+        }
+        //Levels below are empty now, and the ones above don't change, so every lookahead stays valid
+        let next = self.levels[level + 1..].iter().find(|next| next.len > 0);
+        self.levels[level] = Level::build(keys, next);
+        self.len += 1;
+    }
+
+    pub fn lower_bound(&self, key: i32) -> Option<i32> {
+        let mut lower_bound = None;
+        let mut window: Option<usize> = None;
+
+        for level in self.levels.iter().filter(|level| level.len > 0) {
+            let start = window.unwrap_or(0);
+            let end = window.map_or(level.entries.len(), |start| (start + LOOKAHEAD_STRIDE).min(level.entries.len()));
+            let found = level.entries[start..end].partition_point(|entry| entry.key <= key);
+
+            window = Some(if found == 0 {
+                0
+            } else {
+                let entry = &level.entries[start + found - 1];
+                lower_bound = lower_bound.max(entry.lower_bound);
+                entry.window_start
+            });
+        }
+
+        lower_bound
+    }
+
+    pub fn contains(&self, key: i32) -> bool {
+        self.lower_bound(key) == Some(key)
+    }
+
+    // Full levels have a power of two keys, so each one becomes a vEB search tree without padding
+    pub fn freeze(&self) -> FrozenCola {
+        let levels = self.levels.iter()
+            .filter(|level| level.len > 0)
+            .map(|level| SearchTree::new(level.keys(), level.len).unwrap())
+            .collect();
+        FrozenCola { levels, len: self.len }
+    }
+}
+
+impl Default for Cola {
+    fn default() -> Self {
+        Cola::new()
+    }
+}
+
+// Read-only snapshot of a Cola with a vEB search tree per level
+pub struct FrozenCola {
+    levels: Vec<SearchTree>,
+    len: usize,
+}
+
+impl FrozenCola {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn lower_bound(&self, key: i32) -> Option<i32> {
+        self.levels.iter()
+            .filter_map(|level| match level.search(key) {
+                SearchTreeIndex::NotInTree => None,
+                SearchTreeIndex::Leaf { index, .. } => Some(level.key_at(index as usize))
+            })
+            .max()
+    }
+
+    pub fn contains(&self, key: i32) -> bool {
+        self.lower_bound(key) == Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::cola::Cola;
+
+    #[test]
+    fn levels_follow_the_binary_representation_of_the_length() {
+        let mut cola = Cola::new();
+        for key in 0..1000 {
+            cola.insert(key);
+            let lengths: Vec<usize> = cola.levels.iter().map(|level| level.len).collect();
+            for (level, len) in lengths.iter().enumerate() {
+                assert_eq!(*len, cola.len() & (1 << level), "Level {} after {} inserts", level, cola.len());
+            }
+        }
+    }
+
+    #[test]
+    fn insert_and_find_lower_bounds() {
+        let mut cola = Cola::new();
+        assert_eq!(cola.lower_bound(10), None, "Empty array");
+
+        for key in (0..1000).rev().map(|i| i*3) {
+            cola.insert(key);
+        }
+        cola.insert(300);
+
+        assert_eq!(cola.len(), 1001);
+        assert_eq!(cola.lower_bound(-1), None, "Key smaller than every key");
+        assert_eq!(cola.lower_bound(301), Some(300), "Key not in the array");
+        assert_eq!(cola.lower_bound(5000), Some(2997), "Key greater than every key");
+        assert!(cola.contains(300));
+        assert!(!cola.contains(301));
+    }
+
+    #[test]
+    fn random_inserts_match_btreemap() {
+        let mut rng = StdRng::seed_from_u64(31);
+        let mut cola = Cola::new();
+        let mut expected = BTreeMap::new();
+
+        for _ in 0..5000 {
+            let key = rng.gen_range(-20_000..20_000);
+            cola.insert(key);
+            *expected.entry(key).or_insert(0) += 1;
+
+            let probe = rng.gen_range(-21_000..21_000);
+            assert_eq!(cola.lower_bound(probe), expected.range(..=probe).next_back().map(|(&key, _)| key),
+                       "Lower bound of {}", probe);
+        }
+
+        let frozen = cola.freeze();
+        assert_eq!(frozen.len(), cola.len());
+        for probe in -21_000..21_000 {
+            assert_eq!(frozen.lower_bound(probe), cola.lower_bound(probe), "Lower bound of {}", probe);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+// Timing tests after dudect. `cargo test` only runs a quick smoke test of the ct helpers that catches gross
+// leaks. The statistical runs over the oblivious structures take long and need a quiet machine, so they are
+// ignored: run them with `cargo test --release -- --ignored`.
+use std::time::Instant;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+// dudect flags a leak once the t statistic passes 4.5
+pub const LEAK_THRESHOLD: f64 = 4.5;
+
+// Welch's t-test between two classes of measurements, with Welford's running mean and variance
+#[derive(Default)]
+pub struct WelchTest {
+    count: [f64; 2],
+    mean: [f64; 2],
+    squared_distances: [f64; 2]
+}
+
+impl WelchTest {
+    pub fn new() -> WelchTest {
+        WelchTest::default()
+    }
+
+    pub fn push(&mut self, class: usize, measurement: f64) {
+        self.count[class] += 1.0;
+        let distance = measurement - self.mean[class];
+        self.mean[class] += distance / self.count[class];
+        self.squared_distances[class] += distance * (measurement - self.mean[class]);
+    }
+
+    pub fn t(&self) -> f64 {
+        let variance = |class: usize| self.squared_distances[class] / (self.count[class] - 1.0);
+        let standard_error = (variance(0) / self.count[0] + variance(1) / self.count[1]).sqrt();
+        if standard_error == 0.0 {
+            0.0
+        } else {
+            (self.mean[0] - self.mean[1]) / standard_error
+        }
+    }
+}
+
+//Times `run` for two classes of inputs in a random order, like dudect by Reparaz, Balasch and Verbauwhede,
+//and returns the absolute t statistic. `input` makes an input of the given class, and all of them are made
+//before timing starts, so only `run` is measured and the cost of making inputs can't differ between classes.
+//Measurements above the 90th percentile are cropped, since interrupts and cache misses only add to the time.
+pub fn timing_leak<I>(measurements: usize, seed: u64, mut input: impl FnMut(usize) -> I, mut run: impl FnMut(&I)) -> f64 {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let inputs: Vec<(usize, I)> = (0..measurements).map(|_| {
+        let class = rng.gen_range(0..2);
+        (class, input(class))
+    }).collect();
+
+    let mut samples: Vec<(usize, f64)> = inputs.iter().map(|(class, input)| {
+        let start = Instant::now();
+        run(input);
+        (*class, start.elapsed().as_nanos() as f64)
+    }).collect();
+
+    let mut times: Vec<f64> = samples.iter().map(|&(_, time)| time).collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let crop = times[(times.len() * 9 / 10).min(times.len() - 1)];
+    samples.retain(|&(_, time)| time <= crop);
+
+    let mut test = WelchTest::new();
+    samples.into_iter().for_each(|(class, time)| test.push(class, time));
+    test.t().abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hint::black_box;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::ct::{self, WelchTest, timing_leak, LEAK_THRESHOLD};
+    use crate::search_tree::SearchTree;
+    use crate::oblivious::{batch_lookup, ObliviousDictionary};
+
+    #[test]
+    fn welch_test_separates_shifted_distributions() {
+        let mut rng = StdRng::seed_from_u64(45);
+        let mut same = WelchTest::new();
+        let mut shifted = WelchTest::new();
+        for _ in 0..10_000 {
+            let class = rng.gen_range(0..2);
+            let noise = rng.gen_range(0.0..100.0);
+            same.push(class, 1000.0 + noise);
+            shifted.push(class, 1000.0 + noise + class as f64 * 5.0);
+        }
+
+        assert!(same.t().abs() < LEAK_THRESHOLD, "t = {}", same.t());
+        assert!(shifted.t().abs() > LEAK_THRESHOLD, "t = {}", shifted.t());
+    }
+
+    //Few measurements, likely a debug build and whatever else the machine is doing, so the threshold is loose.
+    //Class 0 always runs on ones, class 1 on random words.
+    #[test]
+    fn ct_helpers_smoke_test() {
+        type Helper = fn(i64, i64) -> i64;
+        let helpers: Vec<(&str, Helper)> = vec![
+            ("select", |a, b| ct::select(a & 1 == 1, a, b)),
+            ("swap", |mut a, mut b| { ct::swap(a & 1 == 1, &mut a, &mut b); a }),
+            ("lt", |a, b| ct::lt(a, b) as i64),
+            ("le", |a, b| ct::le(a, b) as i64),
+            ("gt", |a, b| ct::gt(a, b) as i64),
+            ("ge", |a, b| ct::ge(a, b) as i64),
+            ("eq", |a, b| ct::eq(a, b) as i64),
+            ("min", ct::min),
+            ("max", ct::max)
+        ];
+
+        let mut rng = StdRng::seed_from_u64(450);
+        for (seed, (name, helper)) in helpers.into_iter().enumerate() {
+            let t = timing_leak(4_000, seed as u64,
+                |class| (0..64).map(|_| if class == 0 { (1, 1) } else { rng.gen() }).collect::<Vec<(i64, i64)>>(),
+                |pairs| pairs.iter().for_each(|&(a, b)| { black_box(helper(black_box(a), black_box(b))); }));
+            assert!(t < 5.0 * LEAK_THRESHOLD, "{}: t = {}", name, t);
+        }
+    }
+
+    // Timing depends on the machine and its load, so these only run on demand with --ignored, in release mode.
+    // Class 0 always searches for the smallest key, class 1 for random ones.
+    #[test]
+    #[ignore]
+    fn oblivious_search_time_does_not_depend_on_the_key() {
+        let search_tree = SearchTree::from_sorted((0..4096).map(|i| i*2), 4096).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let t = timing_leak(20_000, 2,
+            |class| if class == 0 { 0 } else { rng.gen_range(-100..9000) },
+            |&key| { black_box(search_tree.search_oblivious(black_box(key))); });
+        assert!(t < LEAK_THRESHOLD, "t = {}", t);
+    }
+
+    #[test]
+    #[ignore]
+    fn oblivious_batch_lookup_time_does_not_depend_on_the_keys() {
+        let search_tree = SearchTree::from_sorted((0..1024).map(|i| i*2), 1024).unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let t = timing_leak(5_000, 4,
+            |class| (0..64).map(|_| if class == 0 { 0 } else { rng.gen_range(-100..2100) }).collect::<Vec<i32>>(),
+            |queries| { black_box(batch_lookup(&search_tree, black_box(queries))); });
+        assert!(t < LEAK_THRESHOLD, "t = {}", t);
+    }
+
+    //Class 0 looks up keys that are there, class 1 keys that aren't
+    #[test]
+    #[ignore]
+    fn oblivious_dictionary_time_does_not_depend_on_presence() {
+        let dictionary = ObliviousDictionary::new((0..4096).map(|i| (i*2, i as i64)).collect()).unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let t = timing_leak(20_000, 6,
+            |class| rng.gen_range(0..4096) * 2 + class as i32,
+            |&key| { black_box(dictionary.get(black_box(key))); });
+        assert!(t < LEAK_THRESHOLD, "t = {}", t);
+    }
+}
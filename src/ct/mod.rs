@@ -0,0 +1,138 @@
+mod dudect;
+
+use std::hint::black_box;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+pub use dudect::{WelchTest, timing_leak, LEAK_THRESHOLD};
+
+// Constant-time helpers for keys and records. Conditions are computed with arithmetic on the sign bit and
+// applied with masks, so neither the control flow nor the memory accessed depends on the values.
+pub trait Word: Copy + BitAnd<Output=Self> + BitOr<Output=Self> + BitXor<Output=Self> + Not<Output=Self> {
+    // All ones if the condition holds, all zeros otherwise
+    fn mask(condition: bool) -> Self;
+
+    fn lt(self, other: Self) -> bool;
+
+    fn is_zero(self) -> bool;
+}
+
+impl Word for i32 {
+    #[inline(always)]
+    fn mask(condition: bool) -> i32 {
+        //Keeps the optimizer from turning the masks back into a branch
+        -(black_box(condition) as i32)
+    }
+
+    #[inline(always)]
+    fn lt(self, other: i32) -> bool {
+        (((self as i64) - (other as i64)) >> 63) & 1 == 1
+    }
+
+    #[inline(always)]
+    fn is_zero(self) -> bool {
+        (self as i64).is_zero()
+    }
+}
+
+impl Word for i64 {
+    #[inline(always)]
+    fn mask(condition: bool) -> i64 {
+        -(black_box(condition) as i64)
+    }
+
+    #[inline(always)]
+    fn lt(self, other: i64) -> bool {
+        //Sign of the difference, corrected when it overflows
+        let difference = self.wrapping_sub(other);
+        ((difference ^ ((self ^ other) & (difference ^ self))) >> 63) & 1 == 1
+    }
+
+    #[inline(always)]
+    fn is_zero(self) -> bool {
+        //Only zero has a sign bit of 0 both as itself and negated
+        ((self | self.wrapping_neg()) >> 63) & 1 == 0
+    }
+}
+
+#[inline(always)]
+pub fn select<W: Word>(condition: bool, if_true: W, if_false: W) -> W {
+    let mask = W::mask(condition);
+    (if_true & mask) | (if_false & !mask)
+}
+
+// Both values are read and written whether they are swapped or not
+#[inline(always)]
+pub fn swap<W: Word>(condition: bool, a: &mut W, b: &mut W) {
+    let difference = (*a ^ *b) & W::mask(condition);
+    *a = *a ^ difference;
+    *b = *b ^ difference;
+}
+
+#[inline(always)]
+pub fn lt<W: Word>(a: W, b: W) -> bool {
+    a.lt(b)
+}
+
+#[inline(always)]
+pub fn le<W: Word>(a: W, b: W) -> bool {
+    !b.lt(a)
+}
+
+#[inline(always)]
+pub fn gt<W: Word>(a: W, b: W) -> bool {
+    b.lt(a)
+}
+
+#[inline(always)]
+pub fn ge<W: Word>(a: W, b: W) -> bool {
+    !a.lt(b)
+}
+
+#[inline(always)]
+pub fn eq<W: Word>(a: W, b: W) -> bool {
+    (a ^ b).is_zero()
+}
+
+#[inline(always)]
+pub fn min<W: Word>(a: W, b: W) -> W {
+    select(a.lt(b), a, b)
+}
+
+#[inline(always)]
+pub fn max<W: Word>(a: W, b: W) -> W {
+    select(a.lt(b), b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ct;
+
+    #[test]
+    fn comparisons_match_the_operators() {
+        let keys = [i32::MIN, i32::MIN + 1, -70_000, -1, 0, 1, 5, 70_000, i32::MAX - 1, i32::MAX];
+        for &a in &keys {
+            for &b in &keys {
+                assert_eq!((ct::lt(a, b), ct::le(a, b), ct::gt(a, b), ct::ge(a, b), ct::eq(a, b)),
+                           (a < b, a <= b, a > b, a >= b, a == b), "{} and {}", a, b);
+                assert_eq!((ct::min(a, b), ct::max(a, b)), (a.min(b), a.max(b)));
+
+                let (a, b) = (a as i64 * 3_000_000_000, b as i64 - 7);
+                for &(a, b) in &[(a, b), (i64::MIN, b), (a, i64::MAX), (i64::MAX, i64::MIN)] {
+                    assert_eq!((ct::lt(a, b), ct::ge(a, b), ct::eq(a, b)), (a < b, a >= b, a == b), "{} and {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn select_and_swap() {
+        assert_eq!(ct::select(true, 3, -9), 3);
+        assert_eq!(ct::select(false, 3i64, -9), -9);
+
+        let (mut a, mut b) = (i64::MIN, 42);
+        ct::swap(false, &mut a, &mut b);
+        assert_eq!((a, b), (i64::MIN, 42));
+        ct::swap(true, &mut a, &mut b);
+        assert_eq!((a, b), (42, i64::MIN));
+    }
+}
@@ -1,2 +1,14 @@
 #![feature(portable_simd)]
 pub mod search_tree;
+pub mod cache_sim;
+pub mod static_index;
+pub mod pma;
+pub mod cob_tree;
+pub mod cola;
+pub mod lsm;
+pub mod wal;
+pub mod oram;
+pub mod oblivious;
+pub mod ct;
+pub mod pir;
+pub mod cipher;
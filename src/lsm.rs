@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::search_tree::{SearchTree, SearchTreeIndex};
+
+// Immutable sorted run, None values are tombstones of deleted keys
+struct Run<V> {
+    keys: SearchTree,
+    values: Vec<Option<V>>,
+}
+
+impl <V> Run<V> {
+    fn from_sorted(entries: impl Iterator<Item=(i32, Option<V>)>) -> Option<Run<V>> {
+        let (keys, values): (Vec<i32>, Vec<Option<V>>) = entries.unzip();
+        SearchTree::from_sorted(keys.iter().copied(), keys.len()).ok()
+            .map(|keys| Run { keys, values })
+    }
+
+    fn get(&self, key: i32) -> Option<&Option<V>> {
+        match self.keys.search(key) {
+            SearchTreeIndex::Leaf { index, leaf_number } if self.keys.key_at(index as usize) == key =>
+                Some(&self.values[leaf_number as usize]),
+            _ => None
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item=(i32, &Option<V>)> + '_ {
+        self.keys.leaves().zip(self.values.iter())
+    }
+}
+
+// The merged runs always include the oldest one, so tombstones have nothing left to hide and are dropped
+fn merge_runs<V: Clone>(runs: &[Arc<Run<V>>]) -> Option<Run<V>> {
+    let mut merged = BTreeMap::new();
+    for run in runs {
+        merged.extend(run.entries().map(|(key, value)| (key, value.clone())));
+    }
+    Run::from_sorted(merged.into_iter().filter(|(_, value)| value.is_some()))
+}
+
+// Key/value store over immutable vEB search trees, after the log-structured merge tree.
+// Writes go to a sorted buffer, which is flushed into a new run when it fills up. Once there are
+// more than `max_runs` runs, a background thread merges them into one. Reads check the buffer and
+// then the runs from newest to oldest.
+pub struct Store<V> {
+    buffer: BTreeMap<i32, Option<V>>,
+    buffer_capacity: usize,
+    // Oldest first
+    runs: Vec<Arc<Run<V>>>,
+    max_runs: usize,
+    compaction: Option<JoinHandle<(usize, Option<Run<V>>)>>,
+}
+
+impl <V> Store<V> where V: Clone + Send + Sync + 'static {
+    pub fn new(buffer_capacity: usize, max_runs: usize) -> Store<V> {
+        assert!(buffer_capacity > 0, "Buffer must hold at least one write");
+        Store { buffer: BTreeMap::new(), buffer_capacity, runs: Vec::new(), max_runs, compaction: None }
+    }
+
+    pub fn get(&self, key: i32) -> Option<&V> {
+        self.buffer.get(&key)
+            .or_else(|| self.runs.iter().rev().find_map(|run| run.get(key)))
+            .and_then(|value| value.as_ref())
+    }
+
+    pub fn put(&mut self, key: i32, value: V) {
+        self.write(key, Some(value));
+    }
+
+    pub fn delete(&mut self, key: i32) {
+        self.write(key, None);
+    }
+
+    fn write(&mut self, key: i32, value: Option<V>) {
+        self.buffer.insert(key, value);
+        if self.buffer.len() >= self.buffer_capacity {
+            self.flush();
+        }
+    }
+
+    pub fn flush(&mut self) {
+        let buffer = std::mem::take(&mut self.buffer);
+        if let Some(run) = Run::from_sorted(buffer.into_iter()) {
+            self.runs.push(Arc::new(run));
+        }
+
+        if self.compaction.as_ref().is_some_and(|compaction| compaction.is_finished()) {
+            self.finish_compaction();
+        }
+        if self.compaction.is_none() && self.runs.len() > self.max_runs {
+            let runs = self.runs.clone();
+            self.compaction = Some(thread::spawn(move || (runs.len(), merge_runs(&runs))));
+        }
+    }
+
+    // Waits for a running compaction and installs the merged run
+    pub fn finish_compaction(&mut self) {
+        if let Some(compaction) = self.compaction.take() {
+            let (merged_runs, merged) = compaction.join().expect("Compaction panicked");
+            //Runs flushed during the compaction are newer than the merged ones
+            self.runs.splice(0..merged_runs, merged.map(Arc::new));
+        }
+    }
+
+    pub fn number_of_runs(&self) -> usize {
+        self.runs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::lsm::Store;
+
+    #[test]
+    fn reads_see_the_newest_write() {
+        let mut store = Store::new(4, 8);
+        (0..10).for_each(|key| store.put(key, key * 10));
+        store.put(3, 31);
+        store.delete(5);
+        store.flush();
+
+        assert_eq!(store.get(3), Some(&31), "Overwritten in a newer run");
+        assert_eq!(store.get(5), None, "Tombstone hides the older value");
+        assert_eq!(store.get(9), Some(&90));
+        assert_eq!(store.get(10), None, "Never written");
+    }
+
+    #[test]
+    fn compaction_merges_runs_and_drops_tombstones() {
+        let mut store = Store::new(2, 2);
+        (0..6).for_each(|key| store.put(key, key));
+        store.delete(0);
+        store.delete(1);
+        store.flush();
+        store.finish_compaction();
+        store.flush();
+
+        assert!(store.number_of_runs() <= 2, "Runs: {}", store.number_of_runs());
+        assert_eq!(store.get(0), None);
+        assert_eq!(store.get(1), None);
+        assert_eq!(store.get(2), Some(&2));
+        assert_eq!(store.get(5), Some(&5));
+    }
+
+    #[test]
+    fn random_operations_match_btreemap() {
+        let mut rng = StdRng::seed_from_u64(32);
+        let mut store = Store::new(64, 4);
+        let mut expected = BTreeMap::new();
+
+        for i in 0..20_000 {
+            let key = rng.gen_range(0..2000);
+            if rng.gen_bool(0.7) {
+                store.put(key, i);
+                expected.insert(key, i);
+            } else {
+                store.delete(key);
+                expected.remove(&key);
+            }
+
+            let probe = rng.gen_range(0..2000);
+            assert_eq!(store.get(probe), expected.get(&probe), "Value of {}", probe);
+        }
+
+        store.finish_compaction();
+        for key in 0..2000 {
+            assert_eq!(store.get(key), expected.get(&key), "Value of {}", key);
+        }
+    }
+}
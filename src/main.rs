@@ -1,7 +1,4 @@
-#![feature(portable_simd)]
-mod search_tree;
-
-use search_tree::SearchTree;
+use obliviousdb::search_tree::SearchTree;
 
 fn main() {
     let array: Vec<i32> = (0..8).collect();
@@ -0,0 +1,93 @@
+use crate::ct;
+use crate::oblivious::{bitonic_sort_with_probe, sort::sort_by_rank_with_probe, Record};
+use crate::search_tree::{SearchTree, SearchTreeIndex, Probe, NoProbe, index_of_leaf};
+
+pub fn batch_lookup(tree: &SearchTree, queries: &[i32]) -> Vec<SearchTreeIndex> {
+    batch_lookup_with_probe(tree, queries, &mut NoProbe)
+}
+
+//Same results as SearchTree::search for every query, with memory accesses that only depend on the number of
+//queries and live leaves. Leaves and queries are sorted together, with a leaf before a query of the same key,
+//so a scan can hand each query the last leaf before it. A second sort puts the queries back in their order.
+//The probe sees the slots of both sorts and of the scan.
+pub fn batch_lookup_with_probe<P: Probe>(tree: &SearchTree, queries: &[i32], probe: &mut P) -> Vec<SearchTreeIndex> {
+    //Keys are shifted to make room for a bit telling queries from leaves
+    let leaves = (0..tree.count())
+        .filter(|&leaf_number| !tree.is_deleted(leaf_number))
+        .map(|leaf_number| Record { key: (tree.leaf(leaf_number) as i64) << 1, value: leaf_number as i64 });
+    let queries_in_order = queries.iter().enumerate()
+        .map(|(position, &query)| Record { key: ((query as i64) << 1) | 1, value: position as i64 });
+    let mut records: Vec<Record> = leaves.chain(queries_in_order).collect();
+
+    //The network isn't stable, so leaves of the same key are ranked by their leaf number too,
+    //and a query gets the last of them like in SearchTree::search
+    let ranks: Vec<i64> = records.iter().map(|record| (record.key << 31) | (record.value & 0x7FFF_FFFF)).collect();
+    sort_by_rank_with_probe(&mut records, &ranks, probe);
+
+    //Queries keep their position in the high half of the value and get the leaf number plus one in the low half,
+    //0 if no leaf comes before them. Leaves sort after the queries in the second sort.
+    let mut last_leaf = 0;
+    for (slot, record) in records.iter_mut().enumerate() {
+        probe.touch(slot);
+        let is_query = ct::eq(record.key & 1, 1);
+        last_leaf = ct::select(is_query, last_leaf, record.value + 1);
+        record.value = ct::select(is_query, (record.value << 32) | last_leaf, record.value);
+        record.key = ct::select(is_query, record.value >> 32, i64::MAX);
+    }
+
+    bitonic_sort_with_probe(&mut records, probe);
+
+    records[..queries.len()].iter().map(|record| {
+        let leaf_number = (record.value & 0xFFFF_FFFF) as i32 - 1;
+        if leaf_number < 0 {
+            SearchTreeIndex::NotInTree
+        } else {
+            SearchTreeIndex::Leaf { index: index_of_leaf(leaf_number, tree.height()), leaf_number }
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oblivious::{batch_lookup, batch_lookup_with_probe};
+    use crate::search_tree::{SearchTree, Trace};
+
+    #[test]
+    fn batch_lookup_matches_search() {
+        let mut search_tree = SearchTree::from_sorted((0..200).map(|i| i*5 - 100), 200).unwrap();
+        (0..50).for_each(|key| { search_tree.delete(key); });
+
+        let queries: Vec<i32> = (0..300).map(|i| ((i * 7919) % 1200) - 150).chain(vec![i32::MIN, i32::MAX]).collect();
+        let results = batch_lookup(&search_tree, &queries);
+        for (query, result) in queries.iter().zip(results) {
+            assert_eq!(result, search_tree.search(*query), "Searching for {}", query);
+        }
+        assert!(batch_lookup(&search_tree, &[]).is_empty());
+    }
+
+    #[test]
+    fn repeated_keys_give_the_last_leaf_like_search() {
+        let mut search_tree = SearchTree::from_sorted((0..64).map(|i| i / 8), 64).unwrap();
+        search_tree.delete(5);
+
+        let queries: Vec<i32> = (-1..10).collect();
+        for (query, result) in queries.iter().zip(batch_lookup(&search_tree, &queries)) {
+            assert_eq!(result, search_tree.search(*query), "Searching for {}", query);
+        }
+    }
+
+    #[test]
+    fn trace_only_depends_on_the_sizes() {
+        let search_tree = SearchTree::from_sorted((0..100).map(|i| i*2), 100).unwrap();
+        let trace_of = |queries: Vec<i32>| {
+            let mut trace = Trace(Vec::new());
+            batch_lookup_with_probe(&search_tree, &queries, &mut trace);
+            trace.0
+        };
+
+        let expected = trace_of((0..40).collect());
+        assert_eq!(trace_of(vec![-1; 40]), expected, "Nothing in the tree");
+        assert_eq!(trace_of(vec![99; 40]), expected, "The same query");
+        assert_eq!(trace_of((0..40).map(|i| 1000 - i * 31).collect()), expected, "Unsorted queries");
+    }
+}
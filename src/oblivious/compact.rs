@@ -0,0 +1,92 @@
+use crate::oblivious::{sort::sort_by_rank_with_probe, Record};
+use crate::search_tree::{SearchTree, Probe, NoProbe};
+
+pub fn compact(records: &mut [Record], marked: &[bool]) -> usize {
+    compact_with_probe(records, marked, &mut NoProbe)
+}
+
+//Moves the marked records to the front and returns how many there are. Both parts keep their order, since
+//records are sorted on the mark and then their position with the bitonic network, so the slots accessed
+//only depend on the number of records. Only the returned count tells something about the marks.
+pub fn compact_with_probe<P: Probe>(records: &mut [Record], marked: &[bool], probe: &mut P) -> usize {
+    assert_eq!(records.len(), marked.len(), "A mark for each record");
+    let ranks: Vec<i64> = marked.iter().enumerate()
+        .map(|(position, &is_marked)| ((!is_marked as i64) << 32) | position as i64)
+        .collect();
+    sort_by_rank_with_probe(records, &ranks, probe);
+    marked.iter().map(|&is_marked| is_marked as usize).sum()
+}
+
+//Tree of the live leaves that `keep` holds for, without revealing which ones. `keep` is called for every leaf
+//in order, deleted or not, and should be constant-time itself. Err if no leaf is kept.
+pub fn filter_search_tree(tree: &SearchTree, mut keep: impl FnMut(i32) -> bool) -> Result<SearchTree, ()> {
+    let mut records: Vec<Record> = (0..tree.count())
+        .map(|leaf_number| Record { key: tree.leaf(leaf_number) as i64, value: 0 })
+        .collect();
+    let marked: Vec<bool> = (0..tree.count())
+        .map(|leaf_number| keep(tree.leaf(leaf_number)) & !tree.is_deleted(leaf_number))
+        .collect();
+
+    let kept = compact(&mut records, &marked);
+    SearchTree::from_sorted(records[..kept].iter().map(|record| record.key as i32), kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::oblivious::{compact, compact_with_probe, filter_search_tree, Record};
+    use crate::search_tree::{SearchTree, Trace};
+
+    #[test]
+    fn marked_records_move_to_the_front_in_order() {
+        let mut rng = StdRng::seed_from_u64(50);
+        for size in (0..40).chain(vec![100, 257]) {
+            let records: Vec<Record> = (0..size).map(|i| Record { key: rng.gen_range(-10..10), value: i }).collect();
+            let marked: Vec<bool> = (0..size).map(|_| rng.gen_bool(0.3)).collect();
+
+            let mut compacted = records.clone();
+            let count = compact(&mut compacted, &marked);
+
+            let with_mark = |mark| records.iter().zip(&marked)
+                .filter(move |&(_, &is_marked)| is_marked == mark)
+                .map(|(&record, _)| record);
+            assert_eq!(count, with_mark(true).count(), "{} records", size);
+            let expected: Vec<Record> = with_mark(true).chain(with_mark(false)).collect();
+            assert_eq!(compacted, expected, "{} records", size);
+        }
+    }
+
+    #[test]
+    fn trace_does_not_depend_on_the_marks() {
+        let trace_of = |marked: &[bool]| {
+            let mut records: Vec<Record> = (0..60).map(|i| Record { key: i, value: i }).collect();
+            let mut trace = Trace(Vec::new());
+            compact_with_probe(&mut records, marked, &mut trace);
+            trace.0
+        };
+
+        let expected = trace_of(&[false; 60]);
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..5 {
+            let marked: Vec<bool> = (0..60).map(|_| rng.gen()).collect();
+            assert_eq!(trace_of(&marked), expected);
+        }
+        assert_eq!(trace_of(&[true; 60]), expected);
+    }
+
+    #[test]
+    fn filter_leaves_before_rebuilding() {
+        let mut search_tree = SearchTree::from_sorted(0..200, 200).unwrap();
+        (50..60).for_each(|key| { search_tree.delete(key); });
+
+        let even = filter_search_tree(&search_tree, |key| key % 2 == 0).unwrap();
+        assert!(even.leaves().eq((0..200).filter(|key| key % 2 == 0 && !(50..60).contains(key))));
+        assert_eq!(even.count(), 95);
+        assert!(even.contains(198));
+        assert!(!even.contains(52));
+
+        assert!(filter_search_tree(&search_tree, |_| false).is_err(), "Nothing kept");
+    }
+}
@@ -0,0 +1,91 @@
+use crate::ct::{self, Word};
+use crate::search_tree::{SearchTree, Probe, NoProbe};
+
+// Exact-match lookups without hashing. The keys are in a vEB tree and every slot holds a key and its value,
+// in the order of the leaves. Building it isn't oblivious, only the lookups are.
+pub struct ObliviousDictionary<V> {
+    tree: SearchTree,
+    slots: Vec<(i32, V)>
+}
+
+impl <V: Word> ObliviousDictionary<V> {
+    //Err if there are no entries or a key is there twice
+    pub fn new(mut entries: Vec<(i32, V)>) -> Result<ObliviousDictionary<V>, ()> {
+        entries.sort_by_key(|&(key, _)| key);
+        if entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(())
+        }
+
+        let tree = SearchTree::from_sorted(entries.iter().map(|&(key, _)| key), entries.len())?;
+        Ok(ObliviousDictionary { tree, slots: entries })
+    }
+
+    pub fn get(&self, key: i32) -> Option<V> {
+        self.get_with_probe(key, &mut NoProbe)
+    }
+
+    //Descends the whole tree like search_oblivious and then reads every slot, keeping the one of the lower bound
+    //with masks, so present and missing keys read the same memory in the same order. The probe sees the tree's
+    //nodes by their index and the slots after them.
+    pub fn get_with_probe<P: Probe>(&self, key: i32, probe: &mut P) -> Option<V> {
+        let (found, leaf_number) = self.tree.lower_bound_oblivious_with_probe(key, probe);
+
+        let first_slot = (1 << self.tree.height()) - 1;
+        let mut present = false;
+        let mut value = V::mask(false);
+        for (slot, &(slot_key, slot_value)) in self.slots.iter().enumerate() {
+            probe.touch(first_slot + slot);
+            let is_match = found & ct::eq(slot as i32, leaf_number) & ct::eq(slot_key, key);
+            present |= is_match;
+            value = ct::select(is_match, slot_value, value);
+        }
+
+        //Only the result itself tells whether the key was there
+        if present { Some(value) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::oblivious::ObliviousDictionary;
+    use crate::search_tree::Trace;
+
+    #[test]
+    fn lookups_match_a_hash_map() {
+        let mut rng = StdRng::seed_from_u64(49);
+        let map: HashMap<i32, i64> = (0..700).map(|_| (rng.gen_range(-5000..5000), rng.gen())).collect();
+        let dictionary = ObliviousDictionary::new(map.iter().map(|(&key, &value)| (key, value)).collect()).unwrap();
+
+        for key in -5100..5100 {
+            assert_eq!(dictionary.get(key), map.get(&key).copied(), "Looking up {}", key);
+        }
+        assert_eq!(dictionary.get(i32::MIN), None);
+        assert_eq!(dictionary.get(i32::MAX), None);
+    }
+
+    #[test]
+    fn duplicate_or_no_keys() {
+        assert!(ObliviousDictionary::new(vec![(1, 10), (2, 20), (1, 30)]).is_err());
+        assert!(ObliviousDictionary::<i32>::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn present_and_missing_keys_have_the_same_trace() {
+        let dictionary = ObliviousDictionary::new((0..100).map(|i| (i*3, i as i64)).collect()).unwrap();
+        let trace_of = |key| {
+            let mut trace = Trace(Vec::new());
+            dictionary.get_with_probe(key, &mut trace);
+            trace.0
+        };
+
+        let expected = trace_of(0);
+        assert_eq!(expected.len(), 2*128 - 1 + 100, "Every node of the padded tree and every slot once");
+        for &key in &[-1, 1, 3, 150, 151, 297, 298, 5000, i32::MIN, i32::MAX] {
+            assert_eq!(trace_of(key), expected, "Trace of {}", key);
+        }
+    }
+}
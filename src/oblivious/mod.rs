@@ -0,0 +1,28 @@
+mod sort;
+mod batch;
+mod dictionary;
+mod shuffle;
+mod compact;
+
+use crate::ct;
+
+pub use sort::{bitonic_sort, bitonic_sort_with_probe, build_search_tree};
+pub use batch::{batch_lookup, batch_lookup_with_probe};
+pub use dictionary::ObliviousDictionary;
+pub use shuffle::{shuffle, shuffle_with_probe};
+pub use compact::{compact, compact_with_probe, filter_search_tree};
+
+// Key and payload moved together by the oblivious primitives. Wider than a tree key, so callers can
+// pack tags next to a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub key: i64,
+    pub value: i64
+}
+
+// Swaps the records if the condition holds. Both are read and written either way.
+#[inline(always)]
+fn conditional_swap(condition: bool, a: &mut Record, b: &mut Record) {
+    ct::swap(condition, &mut a.key, &mut b.key);
+    ct::swap(condition, &mut a.value, &mut b.value);
+}
@@ -0,0 +1,75 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::oblivious::{sort::sort_by_rank_with_probe, Record};
+use crate::search_tree::{Probe, NoProbe};
+
+pub fn shuffle(records: &mut [Record], seed: u64) {
+    shuffle_with_probe(records, seed, &mut NoProbe)
+}
+
+//Random permutation of the records, by sorting them on random 64 bit tags with the bitonic network,
+//so the slots accessed only depend on the number of records. The network isn't stable, so records whose
+//tags are the same end up in an order fixed by the network, which happens with a chance of about n^2 / 2^65.
+pub fn shuffle_with_probe<P: Probe>(records: &mut [Record], seed: u64, probe: &mut P) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let tags: Vec<i64> = records.iter().map(|_| rng.gen()).collect();
+    sort_by_rank_with_probe(records, &tags, probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::oblivious::{shuffle, shuffle_with_probe, Record};
+    use crate::search_tree::Trace;
+
+    fn records(size: i64) -> Vec<Record> {
+        (0..size).map(|i| Record { key: i, value: -i }).collect()
+    }
+
+    #[test]
+    fn shuffle_permutes_the_records() {
+        for size in 0..40 {
+            let mut shuffled = records(size);
+            shuffle(&mut shuffled, size as u64);
+            let mut sorted = shuffled.clone();
+            sorted.sort_by_key(|record| record.key);
+            assert_eq!(sorted, records(size), "{} records", size);
+        }
+
+        let (mut a, mut b, mut c) = (records(100), records(100), records(100));
+        shuffle(&mut a, 7);
+        shuffle(&mut b, 7);
+        shuffle(&mut c, 8);
+        assert_eq!(a, b, "Same seed, same permutation");
+        assert_ne!(a, c);
+        assert_ne!(a, records(100));
+    }
+
+    #[test]
+    fn every_permutation_is_about_as_likely() {
+        let mut histogram = HashMap::new();
+        for seed in 0..6000 {
+            let mut shuffled = records(3);
+            shuffle(&mut shuffled, seed);
+            *histogram.entry(shuffled.iter().map(|record| record.key).collect::<Vec<i64>>()).or_insert(0) += 1;
+        }
+        assert_eq!(histogram.len(), 6);
+        assert!(histogram.values().all(|&count| count > 850 && count < 1150), "{:?}", histogram);
+    }
+
+    #[test]
+    fn trace_does_not_depend_on_the_seed_or_the_records() {
+        let trace_of = |records: &mut Vec<Record>, seed| {
+            let mut trace = Trace(Vec::new());
+            shuffle_with_probe(records, seed, &mut trace);
+            trace.0
+        };
+
+        let expected = trace_of(&mut records(50), 0);
+        let mut reversed: Vec<Record> = records(50).into_iter().rev().collect();
+        assert_eq!(trace_of(&mut reversed, 1), expected);
+        assert_eq!(trace_of(&mut vec![Record { key: 3, value: 3 }; 50], 2), expected);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::ct;
+use crate::oblivious::{conditional_swap, Record};
+use crate::search_tree::{SearchTree, Probe, NoProbe};
+
+// Record of the input and what it's sorted by, or padding up to a power of two that sorts after every record
+#[derive(Clone, Copy)]
+struct Slot {
+    record: Record,
+    rank: i64,
+    is_padding: bool
+}
+
+#[inline(always)]
+fn compare_exchange(slots: &mut [Slot], i: usize, j: usize, ascending: bool) {
+    let (left, right) = slots.split_at_mut(j);
+    let (a, b) = (&mut left[i], &mut right[0]);
+
+    let a_is_greater = (a.is_padding & !b.is_padding)
+        | ((a.is_padding == b.is_padding) & ct::gt(a.rank, b.rank));
+    let swap = a_is_greater == ascending;
+    conditional_swap(swap, &mut a.record, &mut b.record);
+    ct::swap(swap, &mut a.rank, &mut b.rank);
+
+    let padding = (a.is_padding ^ b.is_padding) & swap;
+    a.is_padding ^= padding;
+    b.is_padding ^= padding;
+}
+
+pub fn bitonic_sort(records: &mut [Record]) {
+    bitonic_sort_with_probe(records, &mut NoProbe)
+}
+
+//Sorts by key with Batcher's bitonic network. Which slots are compared, and in which order, depends only on
+//the number of records, and every comparison reads and writes both slots. The probe sees both slots of each one.
+pub fn bitonic_sort_with_probe<P: Probe>(records: &mut [Record], probe: &mut P) {
+    let ranks: Vec<i64> = records.iter().map(|record| record.key).collect();
+    sort_by_rank_with_probe(records, &ranks, probe)
+}
+
+//Sorts the records by the rank at the same position, with the same network as bitonic_sort_with_probe
+pub(super) fn sort_by_rank_with_probe<P: Probe>(records: &mut [Record], ranks: &[i64], probe: &mut P) {
+    assert_eq!(records.len(), ranks.len(), "A rank for each record");
+    let size = records.len().next_power_of_two();
+    let padding = Slot { record: Record { key: 0, value: 0 }, rank: 0, is_padding: true };
+    let mut slots: Vec<Slot> = records.iter().zip(ranks)
+        .map(|(&record, &rank)| Slot { record, rank, is_padding: false })
+        .collect();
+    slots.resize(size, padding);
+
+    let mut block = 2;
+    while block <= size {
+        let mut distance = block / 2;
+        while distance > 0 {
+            for i in 0..size {
+                let j = i ^ distance;
+                if j > i {
+                    probe.touch(i);
+                    probe.touch(j);
+                    compare_exchange(&mut slots, i, j, i & block == 0);
+                }
+            }
+            distance /= 2;
+        }
+        block *= 2;
+    }
+
+    for (record, slot) in records.iter_mut().zip(slots) {
+        *record = slot.record;
+    }
+}
+
+//Sorts the keys obliviously and lays them out with create::layout, whose accesses only depend on the count
+pub fn build_search_tree(keys: &[i32]) -> Result<SearchTree, ()> {
+    let mut records: Vec<Record> = keys.iter().map(|&key| Record { key: key as i64, value: 0 }).collect();
+    bitonic_sort(&mut records);
+    SearchTree::from_sorted(records.iter().map(|record| record.key as i32), records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::oblivious::{bitonic_sort, bitonic_sort_with_probe, build_search_tree, Record};
+    use crate::search_tree::{SearchTree, Trace};
+
+    #[test]
+    fn sorts_any_number_of_records() {
+        let mut rng = StdRng::seed_from_u64(43);
+        for size in (0..70).chain(vec![127, 128, 129, 1000]) {
+            let mut records: Vec<Record> = (0..size)
+                .map(|value| Record { key: rng.gen_range(-50..50), value })
+                .collect();
+            let mut expected = records.clone();
+            expected.sort_by_key(|record| record.key);
+
+            bitonic_sort(&mut records);
+            assert!(records.iter().map(|record| record.key).eq(expected.iter().map(|record| record.key)), "{} records", size);
+            let mut values: Vec<i64> = records.iter().map(|record| record.value).collect();
+            values.sort();
+            assert!(values.into_iter().eq(0..size), "Every record is kept once");
+        }
+
+        let mut extremes = vec![Record { key: i64::MAX, value: 1 }, Record { key: i64::MIN, value: 2 }, Record { key: i64::MAX, value: 3 }];
+        bitonic_sort(&mut extremes);
+        assert_eq!(extremes[0], Record { key: i64::MIN, value: 2 }, "Padding sorts after the greatest key");
+    }
+
+    #[test]
+    fn comparisons_only_depend_on_the_number_of_records() {
+        let trace_of = |keys: Vec<i64>| {
+            let mut records: Vec<Record> = keys.into_iter().map(|key| Record { key, value: 0 }).collect();
+            let mut trace = Trace(Vec::new());
+            bitonic_sort_with_probe(&mut records, &mut trace);
+            trace.0
+        };
+
+        let expected = trace_of((0..100).collect());
+        assert_eq!(trace_of((0..100).rev().collect()), expected);
+        assert_eq!(trace_of(vec![7; 100]), expected);
+        assert_eq!(trace_of((0..100).map(|key| (key * 37) % 101).collect()), expected);
+    }
+
+    #[test]
+    fn build_tree_from_unsorted_keys() {
+        let keys: Vec<i32> = (0..300).map(|i| (i * 7919) % 1000).collect();
+        let search_tree = build_search_tree(&keys).unwrap();
+
+        let mut sorted = keys.clone();
+        sorted.sort();
+        let expected = SearchTree::from_sorted(sorted.into_iter(), 300).unwrap();
+        for element in -1..1001 {
+            assert_eq!(search_tree.search(element), expected.search(element), "Searching for {}", element);
+        }
+        assert!(build_search_tree(&[]).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::cipher::{BlockCipher, Sealer};
+use crate::oram::Server;
+
+// Encrypts the buckets before they reach `inner`. A bucket is sealed with a fresh nonce every time it's
+// written, so the server can't tell whether its contents changed. Its bucket number and a version the client
+// counts up on every write are the associated data, so a bucket can't be moved, and an older version of it,
+// which the server could hand back instead, doesn't open either.
+pub struct EncryptedServer<S, C> {
+    inner: S,
+    sealer: Sealer<C>,
+    versions: Vec<u64>
+}
+
+impl <S: Server, C: BlockCipher> EncryptedServer<S, C> {
+    pub fn new(inner: S, sealer: Sealer<C>) -> EncryptedServer<S, C> {
+        EncryptedServer { inner, sealer, versions: Vec::new() }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    fn associated_data(&self, bucket: usize) -> Vec<u8> {
+        let mut associated_data = (bucket as u64).to_le_bytes().to_vec();
+        associated_data.extend_from_slice(&self.versions[bucket].to_le_bytes());
+        associated_data
+    }
+}
+
+impl <S: Server, C: BlockCipher> Server for EncryptedServer<S, C> {
+    //Every bucket gets a nonce of its own, so they're written one by one
+    fn allocate(&mut self, number_of_buckets: usize, bucket: &[u8]) {
+        self.inner.allocate(number_of_buckets, &[]);
+        self.versions = vec![0; number_of_buckets];
+        for number in 0..number_of_buckets {
+            self.write(number, bucket.to_vec());
+        }
+    }
+
+    fn read(&mut self, bucket: usize) -> Result<Vec<u8>, ()> {
+        let sealed = self.inner.read(bucket)?;
+        self.sealer.open(&self.associated_data(bucket), &sealed)
+    }
+
+    fn write(&mut self, bucket: usize, data: Vec<u8>) {
+        self.versions[bucket] += 1;
+        let sealed = self.sealer.seal(&self.associated_data(bucket), &data);
+        self.inner.write(bucket, sealed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::Sealer;
+    use crate::cipher::reference::ReferenceCipher;
+    use crate::oram::{OramSearchTree, PathOram, EncryptedServer, LoggingServer};
+    use crate::search_tree::SearchTree;
+
+    fn encrypted_server(key: u64) -> EncryptedServer<LoggingServer, ReferenceCipher> {
+        EncryptedServer::new(LoggingServer::new(), Sealer::new(ReferenceCipher::new(key)))
+    }
+
+    #[test]
+    fn search_through_encrypted_buckets() {
+        let search_tree = SearchTree::from_sorted((0..200).map(|i| i*5), 200).unwrap();
+        let mut oram_tree = OramSearchTree::new(&search_tree, encrypted_server(3), 4).unwrap();
+
+        for element in -3..1003 {
+            assert_eq!(oram_tree.search(element), Ok(search_tree.search(element)), "Searching for {}", element);
+        }
+
+        //The plaintext keys never reach the server
+        let server = oram_tree.oram().server().inner();
+        let key_bytes = 995i32.to_le_bytes();
+        assert!(server.buckets.iter().all(|bucket| !bucket.windows(4).any(|window| window == key_bytes)));
+    }
+
+    #[test]
+    fn tampered_bucket_is_detected() {
+        let mut oram = PathOram::new(encrypted_server(8), 8, 16, 2);
+        for block in 0..8 {
+            oram.write(block, &[block as u8; 16]).unwrap();
+        }
+
+        //Every path goes through the root
+        oram.server_mut().inner_mut().buckets[0][20] ^= 1;
+        assert!(oram.read(5).is_err());
+
+        let mut oram = PathOram::new(encrypted_server(8), 8, 16, 2);
+        let buckets = &mut oram.server_mut().inner_mut().buckets;
+        buckets.swap(0, 1);
+        assert!(oram.read(0).is_err(), "Moved bucket");
+
+        //Every access writes the root again, so an old copy of it is a rollback
+        let mut oram = PathOram::new(encrypted_server(8), 8, 16, 2);
+        oram.write(1, &[1; 16]).unwrap();
+        let old_root = oram.server().inner().buckets[0].clone();
+        oram.write(1, &[2; 16]).unwrap();
+        oram.server_mut().inner_mut().buckets[0] = old_root;
+        assert!(oram.read(1).is_err(), "Older version of the root");
+    }
+
+    #[test]
+    fn blocks_of_a_tampered_bucket_are_not_read_as_zeros() {
+        let mut oram = PathOram::new(encrypted_server(4), 8, 16, 5);
+        for block in 0..8 {
+            oram.write(block, &[block as u8 + 1; 16]).unwrap();
+        }
+        oram.server_mut().inner_mut().buckets.iter_mut().for_each(|bucket| bucket[20] ^= 1);
+
+        //The first pass finds the tampering, the second must not hand out what was lost with it
+        for _ in 0..2 {
+            for block in 0..8 {
+                let read = oram.read(block);
+                assert!(read.is_err() || read == Ok(vec![block as u8 + 1; 16]), "Block {} read as {:?}", block, read);
+            }
+        }
+    }
+}
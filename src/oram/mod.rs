@@ -0,0 +1,266 @@
+mod tree;
+mod encrypted;
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+pub use tree::OramSearchTree;
+pub use encrypted::EncryptedServer;
+
+pub const BLOCKS_PER_BUCKET: usize = 4;
+
+// Block id of an empty slot in a bucket
+const DUMMY: u64 = u64::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    Read(usize),
+    Write(usize)
+}
+
+// Untrusted storage of the buckets. It sees which buckets are read and written, and the buckets themselves,
+// which PathOram writes in the clear: block ids and data. Wrap it in an EncryptedServer so it only sees opaque bytes.
+pub trait Server {
+    // Replaces the storage with `number_of_buckets` copies of `bucket`
+    fn allocate(&mut self, number_of_buckets: usize, bucket: &[u8]);
+
+    // Err if the bucket can't be trusted, e.g. it was tampered with
+    fn read(&mut self, bucket: usize) -> Result<Vec<u8>, ()>;
+
+    fn write(&mut self, bucket: usize, data: Vec<u8>);
+}
+
+// In-process server that records every physical access, like an adversary watching the storage would
+#[derive(Default)]
+pub struct LoggingServer {
+    buckets: Vec<Vec<u8>>,
+    log: Vec<Access>
+}
+
+impl LoggingServer {
+    pub fn new() -> LoggingServer {
+        LoggingServer::default()
+    }
+
+    pub fn log(&self) -> &[Access] {
+        &self.log
+    }
+
+    pub fn clear_log(&mut self) {
+        self.log.clear();
+    }
+}
+
+impl Server for LoggingServer {
+    fn allocate(&mut self, number_of_buckets: usize, bucket: &[u8]) {
+        self.buckets = vec![bucket.to_vec(); number_of_buckets];
+    }
+
+    fn read(&mut self, bucket: usize) -> Result<Vec<u8>, ()> {
+        self.log.push(Access::Read(bucket));
+        Ok(self.buckets[bucket].clone())
+    }
+
+    fn write(&mut self, bucket: usize, data: Vec<u8>) {
+        self.log.push(Access::Write(bucket));
+        self.buckets[bucket] = data;
+    }
+}
+
+// Path ORAM after Stefanov et al. The buckets form a binary tree with a leaf per block, each holding
+// up to BLOCKS_PER_BUCKET blocks, and every block lives on the path from the root to its leaf in the
+// position map, or in the client's stash. An access reads the whole path of the block, moves the block
+// to a fresh random leaf, and writes the path back with as many stash blocks as fit. The server sees
+// one uniformly random path per access, whichever block it is for and whether it's a read or a write.
+// That only hides the access pattern if the server can't read the buckets, see EncryptedServer.
+pub struct PathOram<S> {
+    server: S,
+    positions: Vec<usize>,
+    stash: BTreeMap<u64, Vec<u8>>,
+    block_size: usize,
+    levels: u32,
+    rng: StdRng,
+    //Set by the first bucket that fails to read. Its blocks are gone, so nothing read after it can be trusted.
+    poisoned: bool
+}
+
+impl <S: Server> PathOram<S> {
+    // Blocks that were never written read as zeros
+    pub fn new(server: S, number_of_blocks: usize, block_size: usize, seed: u64) -> PathOram<S> {
+        let number_of_leaves = number_of_blocks.max(1).next_power_of_two();
+        let levels = number_of_leaves.trailing_zeros() + 1;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let positions = (0..number_of_blocks).map(|_| rng.gen_range(0..number_of_leaves)).collect();
+
+        let mut oram = PathOram { server, positions, stash: BTreeMap::new(), block_size, levels, rng, poisoned: false };
+        let empty_bucket = oram.encode_bucket(Vec::new());
+        oram.server.allocate(2*number_of_leaves - 1, &empty_bucket);
+        oram
+    }
+
+    //Err if the server returned a bucket it shouldn't have, during this access or any before it
+    pub fn read(&mut self, block: usize) -> Result<Vec<u8>, ()> {
+        self.access(block, None)
+    }
+
+    pub fn write(&mut self, block: usize, data: &[u8]) -> Result<(), ()> {
+        assert_eq!(data.len(), self.block_size, "Blocks have a fixed size");
+        self.access(block, Some(data)).map(|_| ())
+    }
+
+    pub fn stash_len(&self) -> usize {
+        self.stash.len()
+    }
+
+    pub fn server(&self) -> &S {
+        &self.server
+    }
+
+    pub fn server_mut(&mut self) -> &mut S {
+        &mut self.server
+    }
+
+    fn number_of_leaves(&self) -> usize {
+        1 << (self.levels - 1)
+    }
+
+    // Bucket at `level` on the path from the root to `leaf`, buckets are numbered level by level
+    fn bucket_on_path(&self, leaf: usize, level: u32) -> usize {
+        (1 << level) - 1 + (leaf >> (self.levels - 1 - level))
+    }
+
+    fn access(&mut self, block: usize, new_data: Option<&[u8]>) -> Result<Vec<u8>, ()> {
+        let leaf = self.positions[block];
+        self.positions[block] = self.rng.gen_range(0..self.number_of_leaves());
+
+        //A bad bucket doesn't stop the access, so the server sees the same path either way.
+        //The blocks in it are lost, so every later access fails too instead of reading them as zeros.
+        for level in 0..self.levels {
+            let bucket = self.server.read(self.bucket_on_path(leaf, level));
+            if bucket.and_then(|bucket| self.decode_bucket(&bucket)).is_err() {
+                self.poisoned = true;
+            }
+        }
+
+        let block_size = self.block_size;
+        let data = self.stash.entry(block as u64).or_insert_with(|| vec![0; block_size]);
+        let old_data = data.clone();
+        if let Some(new_data) = new_data {
+            data.copy_from_slice(new_data);
+        }
+
+        //Deepest buckets first, so blocks sink as far down the path as their own leaf allows
+        for level in (0..self.levels).rev() {
+            let bucket = self.bucket_on_path(leaf, level);
+            let evicted: Vec<u64> = self.stash.keys()
+                .filter(|&&id| self.bucket_on_path(self.positions[id as usize], level) == bucket)
+                .take(BLOCKS_PER_BUCKET)
+                .copied()
+                .collect();
+            let blocks = evicted.into_iter().map(|id| (id, self.stash.remove(&id).unwrap())).collect();
+            let data = self.encode_bucket(blocks);
+            self.server.write(bucket, data);
+        }
+
+        if self.poisoned { Err(()) } else { Ok(old_data) }
+    }
+
+    // BLOCKS_PER_BUCKET slots of a block id and its data, empty ones hold DUMMY and zeros
+    fn encode_bucket(&self, blocks: Vec<(u64, Vec<u8>)>) -> Vec<u8> {
+        let mut bucket = Vec::with_capacity(BLOCKS_PER_BUCKET * (8 + self.block_size));
+        for slot in 0..BLOCKS_PER_BUCKET {
+            match blocks.get(slot) {
+                Some((id, data)) => {
+                    bucket.extend_from_slice(&id.to_le_bytes());
+                    bucket.extend_from_slice(data);
+                }
+                None => {
+                    bucket.extend_from_slice(&DUMMY.to_le_bytes());
+                    bucket.resize(bucket.len() + self.block_size, 0);
+                }
+            }
+        }
+        bucket
+    }
+
+    //Err without touching the stash if the bucket has the wrong size or a block that doesn't exist,
+    //which only a corrupted or malicious server hands back
+    fn decode_bucket(&mut self, bucket: &[u8]) -> Result<(), ()> {
+        if bucket.len() != BLOCKS_PER_BUCKET * (8 + self.block_size) {
+            return Err(())
+        }
+        let slots: Vec<(u64, &[u8])> = bucket.chunks_exact(8 + self.block_size)
+            .map(|slot| (u64::from_le_bytes(slot[..8].try_into().unwrap()), &slot[8..]))
+            .filter(|&(id, _)| id != DUMMY)
+            .collect();
+        if slots.iter().any(|&(id, _)| id >= self.positions.len() as u64) {
+            return Err(())
+        }
+
+        for (id, data) in slots {
+            self.stash.insert(id, data.to_vec());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::oram::{PathOram, LoggingServer, Access};
+
+    #[test]
+    fn random_reads_and_writes_match_an_array() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut oram = PathOram::new(LoggingServer::new(), 100, 8, 7);
+        let mut expected = vec![[0u8; 8]; 100];
+
+        let mut largest_stash = 0;
+        for _ in 0..5000 {
+            let block = rng.gen_range(0..100);
+            if rng.gen_bool(0.5) {
+                let data: [u8; 8] = rng.gen();
+                oram.write(block, &data).unwrap();
+                expected[block] = data;
+            } else {
+                assert_eq!(oram.read(block).unwrap(), expected[block], "Block {}", block);
+            }
+            largest_stash = largest_stash.max(oram.stash_len());
+        }
+        assert!(largest_stash < 30, "Stash grew to {} blocks", largest_stash);
+    }
+
+    #[test]
+    fn every_access_reads_and_writes_one_path() {
+        let mut oram = PathOram::new(LoggingServer::new(), 16, 4, 1);
+        oram.write(3, &[1, 2, 3, 4]).unwrap();
+        oram.read(9).unwrap();
+
+        let log = oram.server().log();
+        assert_eq!(log.len(), 2 * 2 * 5, "Two accesses of a path through 5 levels, read and written back");
+        for path in log.chunks(10) {
+            let reads: Vec<usize> = path[..5].iter().map(|access| match access { Access::Read(bucket) => *bucket, _ => panic!() }).collect();
+            let writes: Vec<usize> = path[5..].iter().rev().map(|access| match access { Access::Write(bucket) => *bucket, _ => panic!() }).collect();
+            assert_eq!(reads, writes, "Path is written back from the leaf up");
+            assert_eq!(reads[0], 0, "Paths start at the root");
+            assert!(reads.windows(2).all(|pair| (pair[1] - 1) / 2 == pair[0]), "Each bucket is a child of the last one");
+        }
+    }
+
+    #[test]
+    fn buckets_with_unknown_blocks_or_the_wrong_size_are_rejected() {
+        let mut oram = PathOram::new(LoggingServer::new(), 8, 16, 3);
+        oram.write(2, &[7; 16]).unwrap();
+        oram.server_mut().buckets.iter_mut().for_each(|bucket| bucket[..8].copy_from_slice(&(u64::MAX - 1).to_le_bytes()));
+        assert!(oram.read(2).is_err(), "Block id past the end");
+        assert!(oram.read(2).is_err(), "Still poisoned");
+
+        let mut oram = PathOram::new(LoggingServer::new(), 8, 16, 3);
+        oram.server_mut().buckets[0].pop();
+        assert!(oram.read(0).is_err(), "Short bucket");
+    }
+}
@@ -0,0 +1,107 @@
+use crate::oram::{PathOram, Server};
+use crate::search_tree::{SearchTree, SearchTreeIndex, block_of_node, key_in_block, search_level_by_level, KEYS_PER_BLOCK};
+
+// vEB node array stored in blocks of consecutive nodes in a Path ORAM. A search reads one node per level,
+// so it makes height ORAM accesses for any key, and a server behind an EncryptedServer can't tell which vEB
+// blocks they were for.
+pub struct OramSearchTree<S> {
+    oram: PathOram<S>,
+    height: u16,
+    count: usize
+}
+
+impl <S: Server> OramSearchTree<S> {
+    //The live leaves of `tree` are laid out again, so deleted leaves don't reach the server. Err if there are none.
+    pub fn new(tree: &SearchTree, server: S, seed: u64) -> Result<OramSearchTree<S>, ()> {
+        let tree = SearchTree::from_sorted_iter(tree.leaves())?;
+        let blocks = tree.to_blocks();
+
+        let mut oram = PathOram::new(server, blocks.len(), KEYS_PER_BLOCK * 4, seed);
+        for (number, block) in blocks.iter().enumerate() {
+            oram.write(number, block)?;
+        }
+
+        Ok(OramSearchTree { oram, height: tree.height(), count: tree.count() })
+    }
+
+    //Same result as SearchTree::search on the tree of the live leaves it was built from. Leaf numbers count live
+    //leaves only, so after a deleted leaf they are smaller than in the original tree.
+    //Err if the server returned a bucket it shouldn't have.
+    //The search goes on after a bad bucket, so the number of accesses doesn't show where it was.
+    pub fn search(&mut self, element: i32) -> Result<SearchTreeIndex, ()> {
+        let oram = &mut self.oram;
+        let mut trusted = true;
+        let result = search_level_by_level(element, self.height, self.count, |index| {
+            match oram.read(block_of_node(index)) {
+                Ok(block) => key_in_block(&block, index),
+                Err(()) => { trusted = false; 0 }
+            }
+        });
+        if trusted { Ok(result) } else { Err(()) }
+    }
+
+    pub fn oram(&self) -> &PathOram<S> {
+        &self.oram
+    }
+
+    pub fn oram_mut(&mut self) -> &mut PathOram<S> {
+        &mut self.oram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oram::{OramSearchTree, LoggingServer, Access};
+    use crate::search_tree::{SearchTree, SearchTreeIndex};
+
+    #[test]
+    fn oram_search_matches_search() {
+        let mut search_tree = SearchTree::from_sorted((0..500).map(|i| i*3), 500).unwrap();
+        search_tree.delete(300);
+        let mut oram_tree = OramSearchTree::new(&search_tree, LoggingServer::new(), 3).unwrap();
+
+        //Leaf numbers after the deleted leaf shift by one, since it isn't in the ORAM's tree
+        let leaf_number = |result| match result {
+            SearchTreeIndex::Leaf { leaf_number, .. } => Some(leaf_number),
+            SearchTreeIndex::NotInTree => None
+        };
+        for element in -5..1510 {
+            let expected = leaf_number(search_tree.search(element))
+                .map(|leaf_number| if leaf_number > 100 { leaf_number - 1 } else { leaf_number });
+            assert_eq!(leaf_number(oram_tree.search(element).unwrap()), expected, "Searching for {}", element);
+        }
+    }
+
+    #[test]
+    fn access_trace_does_not_reveal_the_key() {
+        let search_tree = SearchTree::from_sorted(0..1000, 1000).unwrap();
+        let mut oram_tree = OramSearchTree::new(&search_tree, LoggingServer::new(), 11).unwrap();
+        let levels = 8;
+        let number_of_leaves = 1 << (levels - 1);
+
+        //Leaf at the end of every path the server saw while searching for `key` 500 times
+        let mut leaves_of_paths = |key| {
+            oram_tree.oram_mut().server_mut().clear_log();
+            (0..500).for_each(|_| { oram_tree.search(key).unwrap(); });
+
+            let log = oram_tree.oram().server().log();
+            assert_eq!(log.len(), 500 * 11 * 2 * levels, "11 levels of the search tree, a path read and written for each");
+            let mut histogram = vec![0; number_of_leaves];
+            for path in log.chunks(2 * levels) {
+                assert!(path[..levels].iter().all(|access| matches!(access, Access::Read(_))));
+                assert!(path[levels..].iter().all(|access| matches!(access, Access::Write(_))));
+                if let Access::Read(bucket) = path[levels - 1] {
+                    histogram[bucket - (number_of_leaves - 1)] += 1;
+                }
+            }
+            histogram
+        };
+
+        let expected = (500 * 11 / number_of_leaves) as f64;
+        for &key in &[-1, 0, 499, 999] {
+            let histogram = leaves_of_paths(key);
+            assert!(histogram.iter().all(|&count| (count as f64) > 0.3 * expected && (count as f64) < 2.0 * expected),
+                    "Paths read for {} aren't uniform: {:?}", key, histogram);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::search_tree::{SearchTree, SearchTreeIndex, block_of_node, key_in_block, search_level_by_level};
+
+// Holds a copy of the vEB node array in blocks and answers with the XOR of the blocks a query selects.
+// The servers must not collude: either one alone only sees uniformly random selections.
+pub struct PirServer {
+    blocks: Vec<Vec<u8>>,
+    height: u16,
+    count: usize,
+    log: Vec<Vec<bool>>
+}
+
+impl PirServer {
+    //The live leaves of `tree` are laid out again, so deleted leaves don't reach the server. Err if there are none.
+    pub fn new(tree: &SearchTree) -> Result<PirServer, ()> {
+        let tree = SearchTree::from_sorted_iter(tree.leaves())?;
+        Ok(PirServer { blocks: tree.to_blocks(), height: tree.height(), count: tree.count(), log: Vec::new() })
+    }
+
+    pub fn number_of_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn answer(&mut self, selection: &[bool]) -> Vec<u8> {
+        assert_eq!(selection.len(), self.blocks.len(), "A query selects from every block");
+        self.log.push(selection.to_vec());
+
+        let mut answer = vec![0; self.blocks[0].len()];
+        for (block, _) in self.blocks.iter().zip(selection).filter(|(_, &selected)| selected) {
+            answer.iter_mut().zip(block).for_each(|(byte, block_byte)| *byte ^= block_byte);
+        }
+        answer
+    }
+
+    // Every selection this server was asked for, as it would see them
+    pub fn log(&self) -> &[Vec<bool>] {
+        &self.log
+    }
+}
+
+// Client of the two-server XOR scheme after Chor, Goldreich, Kushilevitz and Sudan. To read a block, it sends
+// a random subset of the blocks to one server and the same subset with the block flipped to the other.
+// Every other block is in both answers or neither, so the XOR of the answers is the block.
+pub struct PirClient {
+    rng: StdRng
+}
+
+impl PirClient {
+    pub fn new(seed: u64) -> PirClient {
+        PirClient { rng: StdRng::seed_from_u64(seed) }
+    }
+
+    pub fn read_block(&mut self, block: usize, servers: (&mut PirServer, &mut PirServer)) -> Vec<u8> {
+        let number_of_blocks = servers.0.number_of_blocks();
+        let selection: Vec<bool> = (0..number_of_blocks).map(|_| self.rng.gen()).collect();
+        let mut flipped = selection.clone();
+        flipped[block] = !flipped[block];
+
+        let answer = servers.0.answer(&selection);
+        servers.1.answer(&flipped).iter().zip(answer).map(|(a, b)| a ^ b).collect()
+    }
+
+    //Same result as SearchTree::search, reading one block per level of the tree from both servers.
+    //Height and count are public, so neither server learns anything about the path.
+    pub fn search(&mut self, element: i32, servers: (&mut PirServer, &mut PirServer)) -> SearchTreeIndex {
+        let (first, second) = servers;
+        let (height, count) = (first.height, first.count);
+        search_level_by_level(element, height, count, |index| {
+            let block = self.read_block(block_of_node(index), (&mut *first, &mut *second));
+            key_in_block(&block, index)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pir::{PirClient, PirServer};
+    use crate::search_tree::SearchTree;
+
+    #[test]
+    fn search_through_two_servers() {
+        let search_tree = SearchTree::from_sorted((0..300).map(|i| i*3 - 20), 300).unwrap();
+        let mut first = PirServer::new(&search_tree).unwrap();
+        let mut second = PirServer::new(&search_tree).unwrap();
+        let mut client = PirClient::new(47);
+
+        for element in -30..900 {
+            assert_eq!(client.search(element, (&mut first, &mut second)), search_tree.search(element), "Searching for {}", element);
+        }
+    }
+
+    #[test]
+    fn each_server_sees_uniformly_random_selections() {
+        let search_tree = SearchTree::from_sorted(0..1000, 1000).unwrap();
+        let mut first = PirServer::new(&search_tree).unwrap();
+        let mut second = PirServer::new(&search_tree).unwrap();
+        let mut client = PirClient::new(3);
+
+        //The same path over and over, yet every block is selected about half the time
+        (0..300).for_each(|_| { client.search(17, (&mut first, &mut second)); });
+        for server in &[&first, &second] {
+            let queries = server.log().len();
+            assert_eq!(queries, 300 * 11, "A query for each of the 11 levels");
+            for block in 0..server.number_of_blocks() {
+                let selected = server.log().iter().filter(|selection| selection[block]).count();
+                assert!((queries * 2 / 5..queries * 3 / 5).contains(&selected), "Block {} selected {} times", block, selected);
+            }
+        }
+
+        for (a, b) in first.log().iter().zip(second.log()) {
+            assert_eq!(a.iter().zip(b).filter(|(a, b)| a != b).count(), 1, "Selections differ in the block read");
+        }
+    }
+}
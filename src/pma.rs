@@ -0,0 +1,370 @@
+use std::ops::{Bound, Range, RangeBounds};
+
+const MIN_SEGMENT_SIZE: usize = 8;
+
+// Density bounds of a window, interpolated between a single segment and the whole array
+const UPPER_DENSITY_OF_SEGMENT: f64 = 1.0;
+const UPPER_DENSITY_OF_ARRAY: f64 = 0.75;
+const LOWER_DENSITY_OF_SEGMENT: f64 = 0.125;
+const LOWER_DENSITY_OF_ARRAY: f64 = 0.25;
+
+// Segments of the packed memory array whose contents were rewritten by an update
+#[derive(Debug, PartialEq)]
+pub enum Rewritten {
+    Segments(Range<usize>),
+    All
+}
+
+// Sorted keys in fixed size segments, each packed to its start with the rest left as a gap.
+// The number of segments is a power of two, so windows of 2^level segments form a binary tree.
+// An update rewrites the smallest window whose density stays within the bounds of its level,
+// which costs O(log^2 n) amortized moves, and a scan reads the keys in order with few gaps.
+// Equal keys are kept, in insertion order.
+pub struct PackedMemoryArray<K> {
+    slots: Vec<K>,
+    lengths: Vec<usize>,
+    //For every segment, the last non-empty segment at or before it
+    last_non_empty: Vec<Option<usize>>,
+    segment_size: usize,
+    count: usize,
+}
+
+impl <K> PackedMemoryArray<K> where K: Ord + Copy + Default {
+    pub fn new() -> PackedMemoryArray<K> {
+        PackedMemoryArray::with_keys(Vec::new(), MIN_SEGMENT_SIZE)
+    }
+
+    fn with_keys(keys: Vec<K>, capacity: usize) -> PackedMemoryArray<K> {
+        let log_capacity = capacity.trailing_zeros() as usize;
+        let segment_size = log_capacity.next_power_of_two().max(MIN_SEGMENT_SIZE).min(capacity);
+        let number_of_segments = capacity / segment_size;
+
+        let mut pma = PackedMemoryArray {
+            slots: vec![K::default(); capacity],
+            lengths: vec![0; number_of_segments],
+            last_non_empty: vec![None; number_of_segments],
+            segment_size,
+            count: keys.len(),
+        };
+        pma.spread(0..number_of_segments, keys);
+        pma
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn number_of_segments(&self) -> usize {
+        self.lengths.len()
+    }
+
+    pub fn segment(&self, segment: usize) -> &[K] {
+        let start = segment * self.segment_size;
+        &self.slots[start..start + self.lengths[segment]]
+    }
+
+    // Smallest key of every segment, None for empty ones. An index over these, like a vEB
+    // search tree built by create::layout, finds the segment of a key without searching the array.
+    pub fn segment_minimums(&self) -> impl Iterator<Item=Option<K>> + '_ {
+        (0..self.number_of_segments()).map(move |segment| self.segment(segment).first().copied())
+    }
+
+    fn height(&self) -> u32 {
+        self.number_of_segments().trailing_zeros()
+    }
+
+    fn threshold(&self, of_segment: f64, of_array: f64, level: u32) -> f64 {
+        let height = self.height();
+        if height == 0 {
+            of_segment
+        } else {
+            of_segment + (of_array - of_segment) * level as f64 / height as f64
+        }
+    }
+
+    fn window(&self, segment: usize, level: u32) -> Range<usize> {
+        let start = (segment >> level) << level;
+        start..start + (1 << level)
+    }
+
+    fn keys_in(&self, window: Range<usize>) -> Vec<K> {
+        window.flat_map(|segment| self.segment(segment).iter().copied()).collect()
+    }
+
+    fn spread(&mut self, window: Range<usize>, keys: Vec<K>) {
+        let number_of_segments = window.len();
+        let mut keys = keys.into_iter();
+
+        for (i, segment) in window.clone().enumerate() {
+            let length = keys.len().div_ceil(number_of_segments - i);
+            let start = segment * self.segment_size;
+            for slot in self.slots[start..start + length].iter_mut() {
+                *slot = keys.next().unwrap();
+            }
+            self.lengths[segment] = length;
+        }
+        self.update_last_non_empty(window);
+    }
+
+    // After the lengths of `window` changed. Empty segments after it point into it, up to the next non-empty one.
+    fn update_last_non_empty(&mut self, window: Range<usize>) {
+        let mut last = window.start.checked_sub(1).and_then(|segment| self.last_non_empty[segment]);
+        for segment in window.start..self.number_of_segments() {
+            if self.lengths[segment] > 0 {
+                if segment >= window.end {
+                    break
+                }
+                last = Some(segment);
+            }
+            self.last_non_empty[segment] = last;
+        }
+    }
+
+    // Segment holding the greatest key less than or equal to `key`
+    pub fn segment_of_lower_bound(&self, key: &K) -> Option<usize> {
+        //Binary search over the segments, an empty one counts as its closest non-empty predecessor
+        let min_at_or_before = |segment: usize| self.last_non_empty[segment].map(|segment| &self.segment(segment)[0]);
+        let mut low = 0;
+        let mut high = self.number_of_segments();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if min_at_or_before(middle).is_none_or(|min| min <= key) { low = middle + 1 } else { high = middle }
+        }
+
+        low.checked_sub(1).and_then(|segment| self.last_non_empty[segment])
+    }
+
+    pub fn lower_bound(&self, key: &K) -> Option<&K> {
+        self.segment_of_lower_bound(key).map(|segment| {
+            let keys = self.segment(segment);
+            &keys[keys.partition_point(|k| k <= key) - 1]
+        })
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.lower_bound(key) == Some(key)
+    }
+
+    pub fn insert(&mut self, key: K) -> Rewritten {
+        let segment = self.segment_of_lower_bound(&key).unwrap_or(0);
+        self.insert_into_segment(segment, key)
+    }
+
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.segment_of_lower_bound(key)
+            .and_then(|segment| self.remove_from_segment(segment, key))
+            .is_some()
+    }
+
+    // For callers that keep their own index over the segments: `segment` holds the greatest key
+    // less than or equal to `key`, or is 0 if there is none
+    pub fn insert_into_segment(&mut self, segment: usize, key: K) -> Rewritten {
+        for level in 0..=self.height() {
+            let window = self.window(segment, level);
+            let capacity = (window.len() * self.segment_size) as f64;
+            let count: usize = self.lengths[window.clone()].iter().sum();
+
+            if (count + 1) as f64 <= self.threshold(UPPER_DENSITY_OF_SEGMENT, UPPER_DENSITY_OF_ARRAY, level) * capacity {
+                let mut keys = self.keys_in(window.clone());
+                keys.insert(keys.partition_point(|k| k <= &key), key);
+                self.spread(window.clone(), keys);
+                self.count += 1;
+                return Rewritten::Segments(window)
+            }
+        }
+
+        let mut keys = self.keys_in(0..self.number_of_segments());
+        keys.insert(keys.partition_point(|k| k <= &key), key);
+        *self = PackedMemoryArray::with_keys(keys, self.capacity() * 2);
+        Rewritten::All
+    }
+
+    // For callers that keep their own index over the segments: `segment` holds the greatest key
+    // less than or equal to `key`. None if `key` isn't in that segment.
+    pub fn remove_from_segment(&mut self, segment: usize, key: &K) -> Option<Rewritten> {
+        let position = self.segment(segment).iter().position(|k| k == key)?;
+        let start = segment * self.segment_size;
+        self.slots.copy_within(start + position + 1..start + self.lengths[segment], start + position);
+        self.lengths[segment] -= 1;
+        self.count -= 1;
+        self.update_last_non_empty(segment..segment + 1);
+
+        for level in 0..=self.height() {
+            let window = self.window(segment, level);
+            let capacity = (window.len() * self.segment_size) as f64;
+            let count: usize = self.lengths[window.clone()].iter().sum();
+
+            if count as f64 >= self.threshold(LOWER_DENSITY_OF_SEGMENT, LOWER_DENSITY_OF_ARRAY, level) * capacity {
+                if level > 0 {
+                    let keys = self.keys_in(window.clone());
+                    self.spread(window.clone(), keys);
+                }
+                return Some(Rewritten::Segments(window))
+            }
+        }
+
+        if self.capacity() > MIN_SEGMENT_SIZE {
+            let keys = self.keys_in(0..self.number_of_segments());
+            *self = PackedMemoryArray::with_keys(keys, self.capacity() / 2);
+            Some(Rewritten::All)
+        } else {
+            Some(Rewritten::Segments(segment..segment + 1))
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&K> + '_ {
+        (0..self.number_of_segments()).flat_map(move |segment| self.segment(segment).iter())
+    }
+
+    // Keys within `range` in order, starting from the segment of its lower end
+    pub fn range<R>(&self, range: R) -> impl Iterator<Item=&K> + '_ where R: RangeBounds<K> {
+        let start = range.start_bound().cloned();
+        let end = range.end_bound().cloned();
+        let first_segment = match start {
+            Bound::Included(start) | Bound::Excluded(start) => self.segment_of_lower_bound(&start).unwrap_or(0),
+            Bound::Unbounded => 0
+        };
+
+        (first_segment..self.number_of_segments())
+            .flat_map(move |segment| self.segment(segment).iter())
+            .skip_while(move |&key| match start {
+                Bound::Included(start) => *key < start,
+                Bound::Excluded(start) => *key <= start,
+                Bound::Unbounded => false
+            })
+            .take_while(move |&key| match end {
+                Bound::Included(end) => *key <= end,
+                Bound::Excluded(end) => *key < end,
+                Bound::Unbounded => true
+            })
+    }
+}
+
+impl <K> Default for PackedMemoryArray<K> where K: Ord + Copy + Default {
+    fn default() -> Self {
+        PackedMemoryArray::new()
+    }
+}
+
+impl <K> Extend<K> for PackedMemoryArray<K> where K: Ord + Copy + Default {
+    fn extend<T: IntoIterator<Item=K>>(&mut self, keys: T) {
+        keys.into_iter().for_each(|key| { self.insert(key); });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::pma::{PackedMemoryArray, Rewritten};
+
+    #[test]
+    fn insert_into_segment_with_space_rewrites_only_that_segment() {
+        let mut pma = PackedMemoryArray::new();
+
+        assert_eq!(pma.insert(5), Rewritten::Segments(0..1));
+        assert_eq!(pma.insert(3), Rewritten::Segments(0..1));
+        assert!(pma.iter().eq(&[3, 5]));
+    }
+
+    #[test]
+    fn full_array_doubles() {
+        let mut pma = PackedMemoryArray::new();
+        pma.extend(0..8);
+        assert_eq!(pma.number_of_segments(), 1);
+
+        assert_eq!(pma.insert(8), Rewritten::All);
+        assert_eq!(pma.number_of_segments(), 2);
+        assert!(pma.iter().copied().eq(0..9));
+    }
+
+    #[test]
+    fn sparse_array_halves() {
+        let mut pma = PackedMemoryArray::new();
+        pma.extend(0..100);
+        let capacity = pma.capacity();
+
+        for key in (0..100).rev() {
+            assert!(pma.remove(&key), "{} is in the array", key);
+        }
+
+        assert!(pma.capacity() < capacity);
+        assert!(pma.is_empty());
+        assert!(!pma.remove(&0), "Array is empty");
+    }
+
+    #[test]
+    fn appends_keep_windows_within_density_bounds() {
+        let mut pma = PackedMemoryArray::new();
+        pma.extend(0..10_000);
+
+        assert!(pma.iter().copied().eq(0..10_000));
+        assert!(pma.len() as f64 <= 0.75 * pma.capacity() as f64, "Array is too dense");
+        assert!(pma.segment_minimums().all(|min| min.is_some()), "Appends leave no segment empty");
+    }
+
+    #[test]
+    fn range_scans() {
+        let mut pma = PackedMemoryArray::new();
+        pma.extend((0..1000).map(|i| i*2));
+
+        assert!(pma.range(100..110).copied().eq(vec![100, 102, 104, 106, 108]));
+        assert!(pma.range(101..=110).copied().eq(vec![102, 104, 106, 108, 110]));
+        assert!(pma.range(1990..).copied().eq(vec![1990, 1992, 1994, 1996, 1998]));
+        assert!(pma.range(..4).copied().eq(vec![0, 2]));
+        assert_eq!(pma.range(-10..0).count(), 0);
+    }
+
+    #[test]
+    fn lower_bounds_across_runs_of_empty_segments() {
+        //The sparse layout is built directly, to force long runs of empty segments between the keys
+        let mut pma = PackedMemoryArray::with_keys(vec![0, 1000, 2000, 3000], 1 << 12);
+        assert_eq!(pma.segment_minimums().filter(|min| min.is_none()).count(), pma.number_of_segments() - 4);
+
+        for key in -1..5000 {
+            let expected = if key < 0 { None } else { Some((key / 1000 * 1000).min(3000)) };
+            assert_eq!(pma.lower_bound(&key).copied(), expected, "Lower bound of {}", key);
+        }
+
+        assert!(pma.remove(&3000));
+        assert_eq!(pma.lower_bound(&5000), Some(&2000));
+    }
+
+    #[test]
+    fn random_operations_match_btreemap_of_counts() {
+        let mut rng = StdRng::seed_from_u64(30);
+        let mut pma = PackedMemoryArray::new();
+        let mut expected = BTreeMap::new();
+
+        for _ in 0..20_000 {
+            let key: i64 = rng.gen_range(-500..500);
+            if rng.gen_bool(0.6) {
+                pma.insert(key);
+                *expected.entry(key).or_insert(0) += 1;
+            } else {
+                let was_present = expected.contains_key(&key);
+                assert_eq!(pma.remove(&key), was_present, "Removing {}", key);
+                if was_present {
+                    *expected.get_mut(&key).unwrap() -= 1;
+                    expected.retain(|_, count| *count > 0);
+                }
+            }
+
+            let probe = rng.gen_range(-600..600);
+            assert_eq!(pma.lower_bound(&probe), expected.range(..=probe).next_back().map(|(key, _)| key),
+                       "Lower bound of {}", probe);
+        }
+
+        assert!(pma.iter().copied().eq(expected.iter().flat_map(|(&key, &count)| std::iter::repeat_n(key, count))));
+    }
+}
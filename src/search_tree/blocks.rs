@@ -0,0 +1,70 @@
+use std::convert::TryInto;
+
+use crate::search_tree::{SearchTree, SearchTreeIndex, index_of_leaf, index_of_node};
+
+// Keys in a block of the node array, a 64 byte cache line
+pub const KEYS_PER_BLOCK: usize = 16;
+
+impl SearchTree {
+    //The node array in blocks of KEYS_PER_BLOCK consecutive nodes, as little endian bytes.
+    //The last block is filled up with zeros.
+    pub fn to_blocks(&self) -> Vec<Vec<u8>> {
+        self.array.chunks(KEYS_PER_BLOCK).map(|keys| {
+            let mut block: Vec<u8> = keys.iter().flat_map(|key| key.to_le_bytes()).collect();
+            block.resize(KEYS_PER_BLOCK * 4, 0);
+            block
+        }).collect()
+    }
+}
+
+pub fn block_of_node(index: usize) -> usize {
+    index / KEYS_PER_BLOCK
+}
+
+pub fn key_in_block(block: &[u8], index: usize) -> i32 {
+    let slot = index % KEYS_PER_BLOCK;
+    i32::from_le_bytes(block[slot*4..slot*4 + 4].try_into().unwrap())
+}
+
+//Same result as SearchTree::search on a tree without deleted leaves, for node arrays that are only read
+//one node at a time, e.g. through an ORAM or PIR. Reads the root and then one node per level,
+//so the number of reads only depends on the height.
+pub fn search_level_by_level(element: i32, height: u16, count: usize, mut key_at: impl FnMut(usize) -> i32) -> SearchTreeIndex {
+    let in_tree = element >= key_at(0);
+
+    let mut position = 0;
+    for depth in 1..height {
+        let right_child = key_at(index_of_node(depth, 2*position + 1, height) as usize);
+        position = 2*position + (element >= right_child) as i32;
+    }
+
+    let leaf_number = position.min(count as i32 - 1);
+    if in_tree {
+        SearchTreeIndex::Leaf { index: index_of_leaf(leaf_number, height), leaf_number }
+    } else {
+        SearchTreeIndex::NotInTree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_tree::SearchTree;
+    use crate::search_tree::blocks::{block_of_node, key_in_block, search_level_by_level, KEYS_PER_BLOCK};
+
+    #[test]
+    fn search_through_blocks() {
+        let search_tree = SearchTree::from_sorted((0..100).map(|i| i*4), 100).unwrap();
+        let blocks = search_tree.to_blocks();
+        assert_eq!(blocks.len(), (2*128 - 1usize).div_ceil(KEYS_PER_BLOCK));
+
+        let mut reads = 0;
+        for element in -2..402 {
+            let result = search_level_by_level(element, search_tree.height(), search_tree.count(), |index| {
+                reads += 1;
+                key_in_block(&blocks[block_of_node(index)], index)
+            });
+            assert_eq!(result, search_tree.search(element), "Searching for {}", element);
+        }
+        assert_eq!(reads, 404 * 8, "The root and a node for each of the other 7 levels");
+    }
+}
@@ -1,4 +1,5 @@
-use crate::search_tree::util::{is_odd, size_of_tree_with_height};
+use crate::search_tree::util::{is_odd, size_of_tree_with_height, size_of_hybrid_tree_with_height,
+                                number_of_leaves_in_tree};
 
 //https://fasterthanli.me/articles/recursive-iterators-rust
 
@@ -8,13 +9,17 @@ struct LayoutSubtreesAndGetMinValues<'a, T>
     generator: T,
     height_of_subtree: u16,
     subtree_number: usize,
+    block_height: Option<u16>,
 }
 
 impl <T> Iterator for LayoutSubtreesAndGetMinValues<'_, T> where T: Iterator<Item=i32> {
     type Item=i32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let size_of_subtree = size_of_tree_with_height(self.height_of_subtree) as usize;
+        let size_of_subtree = match self.block_height {
+            None => size_of_tree_with_height(self.height_of_subtree),
+            Some(block_height) => size_of_hybrid_tree_with_height(self.height_of_subtree, block_height)
+        } as usize;
         let (_, cdr) =
             self.reserved_space.split_at_mut((self.subtree_number*2)*size_of_subtree);
         let (two_subtrees, _) =
@@ -24,8 +29,8 @@ impl <T> Iterator for LayoutSubtreesAndGetMinValues<'_, T> where T: Iterator<Ite
         self.subtree_number+=1;
 
         //ToDo Handle errors
-        if let Ok(min) = layout(first_tree, Box::new(self.generator.by_ref()), self.height_of_subtree) {
-            layout(second_tree, Box::new(self.generator.by_ref()), self.height_of_subtree).unwrap();
+        if let Ok(min) = layout_subtree(first_tree, Box::new(self.generator.by_ref()), self.height_of_subtree, self.block_height) {
+            layout_subtree(second_tree, Box::new(self.generator.by_ref()), self.height_of_subtree, self.block_height).unwrap();
             Some(min)
         } else {
             None
@@ -33,6 +38,42 @@ impl <T> Iterator for LayoutSubtreesAndGetMinValues<'_, T> where T: Iterator<Ite
     }
 }
 
+fn layout_subtree<'a>(
+    reserved_space: &mut [i32],
+    generator:  Box<dyn Iterator<Item=i32> + 'a>,
+    height: u16,
+    block_height: Option<u16>
+) -> Result<i32, ()> {
+    match block_height {
+        None => { layout(reserved_space, generator, height) }
+        Some(block_height) => { layout_hybrid(reserved_space, generator, height, block_height) }
+    }
+}
+
+// Repeats the last element of the generator until `remaining` elements were produced.
+// Pads sorted leaves up to a full binary tree without changing any lower bound.
+pub struct PadWithLast<T> where T: Iterator<Item=i32> {
+    pub generator: T,
+    pub last: Option<i32>,
+    pub remaining: usize,
+}
+
+impl <T> Iterator for PadWithLast<T> where T: Iterator<Item=i32> {
+    type Item=i32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None
+        }
+        self.remaining -= 1;
+
+        if let Some(element) = self.generator.next() {
+            self.last = Some(element);
+        }
+        self.last
+    }
+}
+
 fn layout_tree_of_height_1<'a>(
     reserved_space: &mut [i32],
     mut generator:  Box<dyn Iterator<Item=i32> + 'a>,
@@ -101,7 +142,8 @@ pub fn layout<'a>(
                 reserved_space: bottom_subtree,
                 generator,
                 height_of_subtree: height_of_bottom_subtree,
-                subtree_number: 0
+                subtree_number: 0,
+                block_height: None
             };
 
             layout(top_subtree, Box::new(min_values), height_of_top_subtree)
@@ -109,9 +151,88 @@ pub fn layout<'a>(
     }
 }
 
+// Sets a leaf of a tree laid out by layout, and every node above it that holds the leaf as its minimum.
+// The new value must keep the leaves sorted.
+pub fn update_leaf(reserved_space: &mut [i32], height: u16, leaf_number: i32, value: i32) {
+    if height == 1 {
+        reserved_space[0] = value;
+        return
+    }
+
+    let height_of_bottom_subtree = height / 2;
+    let height_of_top_subtree = height_of_bottom_subtree + if is_odd(height) {1} else {0};
+    let size_of_bottom_subtree = size_of_tree_with_height(height_of_bottom_subtree) as usize;
+    let leaves_in_bottom_subtree = number_of_leaves_in_tree(height_of_bottom_subtree);
+
+    let subtree_number = leaf_number / leaves_in_bottom_subtree;
+    let leaf_number_in_subtree = leaf_number % leaves_in_bottom_subtree;
+
+    let (top_subtree, bottom_subtree) =
+        reserved_space.split_at_mut(size_of_tree_with_height(height_of_top_subtree) as usize);
+    let start = size_of_bottom_subtree * subtree_number as usize;
+    update_leaf(&mut bottom_subtree[start..start+size_of_bottom_subtree],
+                height_of_bottom_subtree, leaf_number_in_subtree, value);
+
+    //The top subtree's leaves are the minimums of the even bottom subtrees
+    if leaf_number_in_subtree == 0 && subtree_number % 2 == 0 {
+        update_leaf(top_subtree, height_of_top_subtree, subtree_number / 2, value);
+    }
+}
+
+fn layout_flat_block<'a>(
+    reserved_space: &mut [i32],
+    mut generator:  Box<dyn Iterator<Item=i32> + 'a>,
+    height: u16
+) -> Result<i32, ()> {
+    for leaf in reserved_space[..number_of_leaves_in_tree(height) as usize].iter_mut() {
+        *leaf = generator.next().ok_or(())?;
+    }
+    Ok(reserved_space[0])
+}
+
+// Same recursion as layout, but subtrees of at most block_height are stored as a flat block of their
+// sorted leaves. A block with 2^(block_height-1) leaves can be sized to a cache line or a page.
+pub fn layout_hybrid<'a>(
+    reserved_space: &mut [i32],
+    generator:  Box<dyn Iterator<Item=i32> + 'a>,
+    height: u16,
+    block_height: u16
+) -> Result<i32, ()> {
+    assert!(block_height > 0, "Blocks must hold at least one leaf");
+
+    if height <= block_height {
+        return layout_flat_block(reserved_space, generator, height)
+    }
+
+    let height_of_bottom_subtree = height / 2;
+    let height_of_top_subtree = height - height_of_bottom_subtree;
+
+    let size_of_top_subtree = size_of_hybrid_tree_with_height(height_of_top_subtree, block_height);
+
+    let (top_subtree, bottom_subtree) =
+        reserved_space.split_at_mut(size_of_top_subtree as usize);
+
+    let min_values = LayoutSubtreesAndGetMinValues {
+        reserved_space: bottom_subtree,
+        generator,
+        height_of_subtree: height_of_bottom_subtree,
+        subtree_number: 0,
+        block_height: Some(block_height)
+    };
+
+    layout_hybrid(top_subtree, Box::new(min_values), height_of_top_subtree, block_height)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::search_tree::create::layout;
+    use crate::search_tree::create::{layout, layout_hybrid, update_leaf, PadWithLast};
+
+    #[test]
+    fn pad_with_last_repeats_last_element() {
+        let padded : Vec<i32> = PadWithLast { generator: vec![1,2,3].into_iter(), last: None, remaining: 5 }.collect();
+
+        assert_eq!(padded, [1,2,3,3,3]);
+    }
 
     #[test]
     fn base_case_layout_tree_of_height_1() {
@@ -141,6 +262,43 @@ mod tests {
         assert_eq!(reserved_space, [0,0,4, 0,0,1, 2,2,3, 4,4,5, 6,6,7]);
     }
 
+    #[test]
+    fn update_leaf_matches_fresh_layout() {
+        let mut leafs : Vec<i32> = (0..32).map(|i| i*4).collect();
+        let mut reserved_space = [0; 63];
+        layout(&mut reserved_space, Box::new(leafs.clone().into_iter()), 6).unwrap();
+
+        for leaf_number in 0..32 {
+            leafs[leaf_number] += 1;
+            update_leaf(&mut reserved_space, 6, leaf_number as i32, leafs[leaf_number]);
+
+            let mut expected = [0; 63];
+            layout(&mut expected, Box::new(leafs.clone().into_iter()), 6).unwrap();
+            assert_eq!(reserved_space, expected, "After updating leaf {}", leaf_number);
+        }
+    }
+
+    #[test]
+    fn hybrid_layout_tree_of_height_4() {
+        let mut reserved_space = [0; 10];
+        let leafs = vec![0,1,2,3,4,5,6,7];
+
+        assert_eq!(layout_hybrid(&mut reserved_space, Box::new(leafs.into_iter()), 4, 2), Ok(0));
+
+        assert_eq!(reserved_space, [0,4, 0,1, 2,3, 4,5, 6,7]);
+    }
+
+    #[test]
+    fn hybrid_layout_with_single_leaf_blocks_is_plain_layout() {
+        let mut plain = [0; 127];
+        let mut hybrid = [0; 127];
+
+        layout(&mut plain, Box::new(0..64), 7).unwrap();
+        layout_hybrid(&mut hybrid, Box::new(0..64), 7, 1).unwrap();
+
+        assert_eq!(plain, hybrid);
+    }
+
     #[test]
     fn recursive_case_layout_tree_of_height_7() {
         let mut reserved_space = [0; 127];
@@ -0,0 +1,131 @@
+use crate::search_tree::SearchTree;
+use crate::search_tree::util::{index_of_node, number_of_leaves_in_tree};
+
+// Node of the implicit full binary tree, addressed by depth and position within the depth.
+// Inner nodes hold the minimum of their subtree. The tree is full, so leaves past count are padding
+// that repeats the last key, and deleted leaves are still there.
+#[derive(Clone, Copy)]
+pub struct Cursor<'a> {
+    tree: &'a SearchTree,
+    depth: u16,
+    position: i32
+}
+
+impl <'a> Cursor<'a> {
+    pub fn root(tree: &'a SearchTree) -> Cursor<'a> {
+        Cursor { tree, depth: 0, position: 0 }
+    }
+
+    pub fn left(&self) -> Option<Cursor<'a>> {
+        self.child(0)
+    }
+
+    pub fn right(&self) -> Option<Cursor<'a>> {
+        self.child(1)
+    }
+
+    fn child(&self, side: i32) -> Option<Cursor<'a>> {
+        if self.is_leaf() {
+            None
+        } else {
+            Some(Cursor { tree: self.tree, depth: self.depth + 1, position: 2*self.position + side })
+        }
+    }
+
+    pub fn parent(&self) -> Option<Cursor<'a>> {
+        if self.depth == 0 {
+            None
+        } else {
+            Some(Cursor { tree: self.tree, depth: self.depth - 1, position: self.position / 2 })
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.depth + 1 == self.tree.height
+    }
+
+    pub fn depth(&self) -> u16 {
+        self.depth
+    }
+
+    pub fn index(&self) -> usize {
+        index_of_node(self.depth, self.position, self.tree.height) as usize
+    }
+
+    pub fn key(&self) -> i32 {
+        self.tree.array[self.index()]
+    }
+
+    pub fn leaf_number(&self) -> Option<i32> {
+        if self.is_leaf() { Some(self.position) } else { None }
+    }
+
+    //Leaves below this node
+    pub fn leaf_numbers(&self) -> std::ops::Range<i32> {
+        let leaves_below = number_of_leaves_in_tree(self.tree.height - self.depth);
+        self.position * leaves_below..(self.position + 1) * leaves_below
+    }
+
+    pub fn is_padding(&self) -> bool {
+        self.leaf_numbers().start as usize >= self.tree.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_tree::{SearchTree, SearchTreeIndex};
+    use crate::search_tree::cursor::Cursor;
+
+    fn leftmost_leaf(cursor: Cursor) -> Cursor {
+        cursor.left().map_or(cursor, leftmost_leaf)
+    }
+
+    #[test]
+    fn walk_down_to_every_leaf_and_back() {
+        let search_tree = SearchTree::from_sorted((0..40).map(|i| i*5), 40).unwrap();
+        let root = Cursor::root(&search_tree);
+        assert_eq!((root.depth(), root.key(), root.index()), (0, 0, 0));
+        assert!(root.parent().is_none());
+
+        for leaf_number in 0..40 {
+            let mut cursor = root;
+            for depth in (0..6).rev() {
+                cursor = if leaf_number & (1 << depth) == 0 { cursor.left() } else { cursor.right() }.unwrap();
+            }
+            assert!(cursor.is_leaf());
+            assert!(cursor.left().is_none() && cursor.right().is_none());
+            assert_eq!(cursor.leaf_number(), Some(leaf_number));
+            assert_eq!(cursor.key(), leaf_number * 5);
+            assert_eq!(search_tree.search(cursor.key()), SearchTreeIndex::Leaf { index: cursor.index() as i32, leaf_number });
+
+            let mut up = cursor;
+            while let Some(parent) = up.parent() {
+                assert_eq!(parent.key(), leftmost_leaf(parent).key(), "Inner nodes hold the minimum of their subtree");
+                up = parent;
+            }
+            assert_eq!(up.index(), root.index());
+        }
+    }
+
+    #[test]
+    fn pruned_scan_of_a_key_range() {
+        fn scan(cursor: Cursor, low: i32, high: i32, keys: &mut Vec<i32>) {
+            if cursor.is_padding() {
+                return
+            }
+            match (cursor.left(), cursor.right()) {
+                (Some(left), Some(right)) => {
+                    //The right child holds the minimum of the right half, so it bounds the left half from above
+                    if right.key() > low { scan(left, low, high, keys) }
+                    if right.key() <= high { scan(right, low, high, keys) }
+                }
+                _ => if (low..=high).contains(&cursor.key()) { keys.push(cursor.key()) }
+            }
+        }
+
+        let search_tree = SearchTree::from_sorted((0..100).map(|i| i*3), 100).unwrap();
+        let mut keys = Vec::new();
+        scan(Cursor::root(&search_tree), 31, 62, &mut keys);
+        assert_eq!(keys, (11..=20).map(|i| i*3).collect::<Vec<i32>>());
+    }
+}
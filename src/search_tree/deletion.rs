@@ -0,0 +1,65 @@
+const BITS: usize = 64;
+
+// One bit per leaf_number, set once the leaf is deleted
+pub struct DeletionBitmap {
+    words: Vec<u64>,
+    deleted: usize
+}
+
+impl DeletionBitmap {
+    pub fn new(count: usize) -> DeletionBitmap {
+        DeletionBitmap { words: vec![0; count.div_ceil(BITS)], deleted: 0 }
+    }
+
+    pub fn is_deleted(&self, leaf_number: usize) -> bool {
+        self.words[leaf_number / BITS] & (1 << (leaf_number % BITS)) != 0
+    }
+
+    pub fn delete(&mut self, leaf_number: usize) {
+        if !self.is_deleted(leaf_number) {
+            self.words[leaf_number / BITS] |= 1 << (leaf_number % BITS);
+            self.deleted += 1;
+        }
+    }
+
+    pub fn deleted(&self) -> usize {
+        self.deleted
+    }
+
+    // Greatest leaf at or before `leaf_number` that isn't deleted, skipping whole words of deleted leaves
+    pub fn last_live_at_or_before(&self, leaf_number: usize) -> Option<usize> {
+        let mut word = leaf_number / BITS;
+        let mut live = !self.words[word] & (u64::MAX >> (BITS - 1 - leaf_number % BITS));
+        loop {
+            if live != 0 {
+                return Some(word * BITS + BITS - 1 - live.leading_zeros() as usize)
+            }
+            if word == 0 {
+                return None
+            }
+            word -= 1;
+            live = !self.words[word];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_tree::deletion::DeletionBitmap;
+
+    #[test]
+    fn last_live_leaf_across_words() {
+        let mut bitmap = DeletionBitmap::new(200);
+        (3..150).for_each(|leaf_number| bitmap.delete(leaf_number));
+        bitmap.delete(3);
+
+        assert_eq!(bitmap.deleted(), 147);
+        assert_eq!(bitmap.last_live_at_or_before(199), Some(199));
+        assert_eq!(bitmap.last_live_at_or_before(149), Some(2));
+        assert_eq!(bitmap.last_live_at_or_before(64), Some(2));
+        assert_eq!(bitmap.last_live_at_or_before(2), Some(2));
+
+        (0..3).for_each(|leaf_number| bitmap.delete(leaf_number));
+        assert_eq!(bitmap.last_live_at_or_before(149), None);
+    }
+}
@@ -0,0 +1,123 @@
+use std::convert::TryInto;
+use rand::Rng;
+
+use crate::cipher::{BlockCipher, Sealer};
+use crate::search_tree::{SearchTree, KEYS_PER_BLOCK};
+use crate::search_tree::deletion::DeletionBitmap;
+use crate::search_tree::util::{number_of_leaves_in_tree, size_of_tree_with_height};
+
+const MAGIC: &[u8; 4] = b"vEBT";
+const HEADER_SIZE: usize = 4 + 2 + 8 + 8;
+
+// Header in the clear and authenticated with every block, then each block of to_blocks sealed on its own.
+// The random id tells files of the same shape apart.
+fn header(height: u16, count: usize, file_id: u64) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&height.to_le_bytes());
+    header.extend_from_slice(&(count as u64).to_le_bytes());
+    header.extend_from_slice(&file_id.to_le_bytes());
+    header
+}
+
+// Binds a block to its header and its place in the array, so blocks can't be swapped or mixed between trees
+fn associated_data(header: &[u8], block_number: usize) -> Vec<u8> {
+    let mut associated_data = header.to_vec();
+    associated_data.extend_from_slice(&(block_number as u64).to_le_bytes());
+    associated_data
+}
+
+impl SearchTree {
+    //The live leaves are laid out again, so deleted leaves aren't written. Err if there are none.
+    pub fn to_encrypted_bytes<C: BlockCipher>(&self, sealer: &Sealer<C>) -> Result<Vec<u8>, ()> {
+        let tree = SearchTree::from_sorted_iter(self.leaves())?;
+        let header = header(tree.height, tree.count, rand::thread_rng().gen());
+
+        let mut bytes = header.clone();
+        for (block_number, block) in tree.to_blocks().iter().enumerate() {
+            bytes.extend_from_slice(&sealer.seal(&associated_data(&header, block_number), block));
+        }
+        Ok(bytes)
+    }
+
+    //Err if any block was changed, moved or left out, or the header doesn't fit the blocks
+    pub fn from_encrypted_bytes<C: BlockCipher>(bytes: &[u8], sealer: &Sealer<C>) -> Result<SearchTree, ()> {
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+            return Err(())
+        }
+        let (header, blocks) = bytes.split_at(HEADER_SIZE);
+        let height = u16::from_le_bytes(header[4..6].try_into().unwrap());
+        let count = u64::from_le_bytes(header[6..14].try_into().unwrap()) as usize;
+        if height == 0 || height > 30 || count == 0 || count > number_of_leaves_in_tree(height) as usize {
+            return Err(())
+        }
+
+        let size = size_of_tree_with_height(height) as usize;
+        let sealed_size = Sealer::<C>::sealed_size(KEYS_PER_BLOCK * 4);
+        if blocks.len() != size.div_ceil(KEYS_PER_BLOCK) * sealed_size {
+            return Err(())
+        }
+
+        let mut array = Vec::with_capacity(size);
+        for (block_number, sealed) in blocks.chunks_exact(sealed_size).enumerate() {
+            let block = sealer.open(&associated_data(header, block_number), sealed)?;
+            array.extend(block.chunks_exact(4).map(|key| i32::from_le_bytes(key.try_into().unwrap())));
+        }
+        array.truncate(size);
+
+        Ok(SearchTree { array: array.into_boxed_slice(), height, count, deleted: DeletionBitmap::new(count) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::Sealer;
+    use crate::cipher::reference::ReferenceCipher;
+    use crate::search_tree::SearchTree;
+
+    #[test]
+    fn encrypted_round_trip() {
+        let mut search_tree = SearchTree::from_sorted((0..300).map(|i| i*2), 300).unwrap();
+        search_tree.delete(100);
+        let sealer = Sealer::new(ReferenceCipher::new(5));
+
+        let bytes = search_tree.to_encrypted_bytes(&sealer).unwrap();
+        let decrypted = SearchTree::from_encrypted_bytes(&bytes, &sealer).unwrap();
+        assert!(decrypted.leaves().eq(search_tree.leaves()));
+        assert_eq!(decrypted.count(), 299);
+        for element in -1..601 {
+            assert_eq!(decrypted.contains(element), search_tree.contains(element), "Searching for {}", element);
+        }
+
+        assert_ne!(search_tree.to_encrypted_bytes(&sealer).unwrap(), bytes, "Fresh nonces");
+        assert!(SearchTree::from_encrypted_bytes(&bytes, &Sealer::new(ReferenceCipher::new(6))).is_err(), "Other key");
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let search_tree = SearchTree::from_sorted(0..40, 40).unwrap();
+        let sealer = Sealer::new(ReferenceCipher::new(9));
+        let bytes = search_tree.to_encrypted_bytes(&sealer).unwrap();
+
+        for offset in 0..bytes.len() {
+            let mut tampered = bytes.clone();
+            tampered[offset] ^= 0x10;
+            assert!(SearchTree::from_encrypted_bytes(&tampered, &sealer).is_err(), "Changed byte {}", offset);
+        }
+
+        let block_size = Sealer::<ReferenceCipher>::sealed_size(64);
+        let mut swapped = bytes.clone();
+        swapped[22..22 + 2*block_size].rotate_left(block_size);
+        assert!(SearchTree::from_encrypted_bytes(&swapped, &sealer).is_err(), "Swapped blocks");
+        assert!(SearchTree::from_encrypted_bytes(&bytes[..bytes.len() - block_size], &sealer).is_err(), "Left out a block");
+
+        let other = SearchTree::from_sorted(100..140, 40).unwrap().to_encrypted_bytes(&sealer).unwrap();
+        let mut mixed = bytes.clone();
+        mixed[22..22 + block_size].copy_from_slice(&other[22..22 + block_size]);
+        assert!(SearchTree::from_encrypted_bytes(&mixed, &sealer).is_err(), "Block of another tree of the same shape");
+
+        //The tree's size doesn't fit an i32 any more
+        let mut too_high = bytes.clone();
+        too_high[4..6].copy_from_slice(&31u16.to_le_bytes());
+        assert!(SearchTree::from_encrypted_bytes(&too_high, &sealer).is_err(), "Height 31");
+    }
+}
@@ -0,0 +1,144 @@
+use crate::search_tree::{SearchTree, SearchTreeIndex, Probe, NoProbe};
+use crate::search_tree::search::{search_for_lower_bound_with_probe, subtree_root_index_generator, Leaf};
+use crate::search_tree::util::{index_of_leaf, number_of_leaves_in_tree, size_of_tree_with_height};
+
+// Leaf of a previous search to start the next one from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Finger {
+    leaf_number: i32
+}
+
+impl From<&SearchTreeIndex> for Finger {
+    fn from(index: &SearchTreeIndex) -> Finger {
+        match index {
+            SearchTreeIndex::Leaf { leaf_number, .. } => Finger { leaf_number: *leaf_number },
+            SearchTreeIndex::NotInTree => Finger { leaf_number: 0 }
+        }
+    }
+}
+
+// Subtree of the recursive layout: index of its root, its height and its first leaf in the whole tree
+struct Subtree {
+    offset: usize,
+    height: u16,
+    first_leaf: i32
+}
+
+// The bottom subtrees holding `leaf_number` at every level of the recursion, from the whole tree down to a base case.
+// Only arithmetic, the array isn't read.
+fn subtrees_holding_leaf(leaf_number: i32, height: u16) -> Vec<Subtree> {
+    let mut subtrees = vec![Subtree { offset: 0, height, first_leaf: 0 }];
+    while let Some(&Subtree { offset, height, first_leaf }) = subtrees.last().filter(|subtree| subtree.height > 3) {
+        let subtree_height = height >> 1;
+        let leaves_in_subtree = number_of_leaves_in_tree(subtree_height);
+        let subtree_number = (leaf_number - first_leaf) / leaves_in_subtree;
+
+        subtrees.push(Subtree {
+            offset: offset + subtree_root_index_generator(height)(subtree_number) as usize,
+            height: subtree_height,
+            first_leaf: first_leaf + leaves_in_subtree * subtree_number
+        });
+    }
+    subtrees
+}
+
+impl SearchTree {
+    pub fn search_from(&self, finger: &Finger, element: i32) -> SearchTreeIndex {
+        self.search_from_with_probe(finger, element, &mut NoProbe)
+    }
+
+    //Climbs from the smallest subtree holding the finger until the lower bound of `element` is in the subtree,
+    //then descends from there. Nearby keys share small subtrees, so the cost grows with the distance to the finger.
+    //A finger past the leaves of this tree, e.g. from another tree, searches from the root.
+    pub fn search_from_with_probe<P: Probe>(&self, finger: &Finger, element: i32, probe: &mut P) -> SearchTreeIndex {
+        match self.lower_bound_from_with_probe(finger.leaf_number, element, probe) {
+            Some(leaf) => self.skip_deleted(leaf),
+            None => SearchTreeIndex::NotInTree
+        }
+    }
+
+    //Last leaf holding at most `element`, deleted or not, or None if every leaf is larger
+    pub(super) fn lower_bound_from_with_probe<P: Probe>(&self, finger: i32, element: i32, probe: &mut P) -> Option<Leaf> {
+        probe.touch(0);
+        if element < self.array[0] {
+            return None
+        }
+
+        let number_of_leaves = number_of_leaves_in_tree(self.height);
+        let subtrees = if (0..self.count as i32).contains(&finger) {
+            subtrees_holding_leaf(finger, self.height)
+        } else {
+            vec![Subtree { offset: 0, height: self.height, first_leaf: 0 }]
+        };
+        let holds_lower_bound = |subtree: &&Subtree, probe: &mut P| {
+            //The root of a subtree holds its first leaf
+            probe.touch(subtree.offset);
+            let next_leaf = subtree.first_leaf + number_of_leaves_in_tree(subtree.height);
+            element >= self.array[subtree.offset] && (next_leaf >= number_of_leaves || {
+                let next_leaf_index = index_of_leaf(next_leaf, self.height) as usize;
+                probe.touch(next_leaf_index);
+                element < self.array[next_leaf_index]
+            })
+        };
+        let subtree = subtrees.iter().rev().find(|subtree| holds_lower_bound(subtree, probe)).unwrap_or(&subtrees[0]);
+
+        let end = subtree.offset + size_of_tree_with_height(subtree.height) as usize;
+        let Leaf { index, leaf_number } = search_for_lower_bound_with_probe(
+            element, subtree.height, &self.array[subtree.offset..end], subtree.offset, probe
+        );
+        Some(self.skip_padding(Leaf {
+            index: subtree.offset as i32 + index,
+            leaf_number: subtree.first_leaf + leaf_number
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::search_tree::{SearchTree, Finger, CountingProbe, SearchTreeIndex};
+
+    #[test]
+    fn finger_search_matches_search() {
+        let mut rng = StdRng::seed_from_u64(38);
+        let mut leaves: Vec<i32> = (0..3000).map(|_| rng.gen_range(0..20_000)).collect();
+        leaves.sort();
+        let search_tree = SearchTree::from_sorted(leaves.into_iter(), 3000).unwrap();
+
+        let mut finger = Finger::from(&SearchTreeIndex::NotInTree);
+        let mut element = -10;
+        for _ in 0..5000 {
+            element = if rng.gen_bool(0.9) { element + rng.gen_range(-20..60) } else { rng.gen_range(-100..20_100) };
+            let result = search_tree.search_from(&finger, element);
+            assert_eq!(result, search_tree.search(element), "Searching for {} from {:?}", element, finger);
+            finger = Finger::from(&result);
+        }
+    }
+
+    #[test]
+    fn finger_of_a_larger_tree() {
+        let large = SearchTree::from_sorted(0..5000, 5000).unwrap();
+        let small = SearchTree::from_sorted((0..40).map(|i| i*10), 40).unwrap();
+        let finger = Finger::from(&large.search(4000));
+
+        for element in -5..410 {
+            assert_eq!(small.search_from(&finger, element), small.search(element), "Searching for {}", element);
+        }
+    }
+
+    #[test]
+    fn nearby_keys_touch_fewer_nodes() {
+        let search_tree = SearchTree::new(0..1 << 16, 1 << 16).unwrap();
+        let finger = Finger::from(&search_tree.search(30_000));
+
+        let mut full_search = CountingProbe(0);
+        search_tree.search_with_probe(30_001, &mut full_search);
+        let mut from_finger = CountingProbe(0);
+        let result = search_tree.search_from_with_probe(&finger, 30_001, &mut from_finger);
+
+        assert_eq!(result, search_tree.search(30_001));
+        assert!(from_finger.0 < full_search.0, "{} touches from the finger, {} from the root", from_finger.0, full_search.0);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::search_tree::create::{layout_hybrid, PadWithLast};
+use crate::search_tree::search::{search_for_lower_bound_hybrid, Leaf, NoProbe, Probe, SearchTreeIndex};
+use crate::search_tree::search::SearchTreeIndex::NotInTree;
+use crate::search_tree::util::{size_of_hybrid_tree_with_height, index_of_leaf_in_hybrid_tree};
+
+// Blocks of 16 leaves fill a 64 byte cache line
+pub const CACHE_LINE_BLOCK_HEIGHT: u16 = 5;
+
+// Van Emde Boas layout down to subtrees of block_height, which are stored as flat sorted blocks of
+// 2^(block_height-1) leaves and scanned with SIMD. Indices in SearchTreeIndex are positions in this layout.
+pub struct HybridSearchTree {
+    array: Box<[i32]>,
+    height: u16,
+    block_height: u16,
+    count: usize
+}
+
+impl HybridSearchTree {
+    pub fn search(&self, element: i32) -> SearchTreeIndex {
+        self.search_with_probe(element, &mut NoProbe)
+    }
+
+    pub fn search_with_probe<P: Probe>(&self, element: i32, probe: &mut P) -> SearchTreeIndex {
+        probe.touch(0);
+        if element >= self.array[0] {
+            let Leaf { index, leaf_number } = self.skip_padding(
+                search_for_lower_bound_hybrid(element, self.height, self.block_height, &self.array, 0, probe)
+            );
+            SearchTreeIndex::Leaf { index, leaf_number }
+        } else {
+            NotInTree
+        }
+    }
+
+    fn skip_padding(&self, leaf: Leaf) -> Leaf {
+        if (leaf.leaf_number as usize) < self.count {
+            leaf
+        } else {
+            let leaf_number = self.count as i32 - 1;
+            Leaf { index: index_of_leaf_in_hybrid_tree(leaf_number, self.height, self.block_height), leaf_number }
+        }
+    }
+
+    pub fn key_at(&self, index: usize) -> i32 {
+        self.array[index]
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.array.len() * std::mem::size_of::<i32>()
+    }
+
+    pub fn new(generator: impl Iterator<Item=i32>, count: usize, block_height: u16) -> Result<HybridSearchTree, ()> {
+        assert_eq!(count.count_ones(), 1,
+                   "Search Tree must be a full binary tree. Number of leaves: {}", count);
+
+        let height = count.trailing_zeros() as u16 + 1;
+        let size = size_of_hybrid_tree_with_height(height, block_height) as usize;
+
+        let mut reserved_space = vec![0; size];
+        layout_hybrid(&mut reserved_space, Box::new(generator), height, block_height).map(
+            move |_i| HybridSearchTree { array: reserved_space.into_boxed_slice(), height, block_height, count }
+        )
+    }
+
+    pub fn from_sorted(generator: impl Iterator<Item=i32>, count: usize, block_height: u16) -> Result<HybridSearchTree, ()> {
+        if count == 0 {
+            return Err(())
+        }
+
+        let padded_count = count.next_power_of_two();
+        let padded = PadWithLast { generator: generator.take(count), last: None, remaining: padded_count };
+
+        HybridSearchTree::new(padded, padded_count, block_height)
+            .map(|search_tree| HybridSearchTree { count, ..search_tree })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_tree::{SearchTree, HybridSearchTree, SearchTreeIndex};
+
+    #[test]
+    fn hybrid_and_plain_trees_find_the_same_leaves() {
+        let leaves : Vec<i32> = (0..200).map(|i| i*5).collect();
+        let search_tree = SearchTree::from_sorted(leaves.iter().copied(), leaves.len()).unwrap();
+
+        for block_height in 1..=9 {
+            let hybrid = HybridSearchTree::from_sorted(leaves.iter().copied(), leaves.len(), block_height).unwrap();
+
+            for element in -3..1003 {
+                let leaf_number = |result| match result {
+                    SearchTreeIndex::NotInTree => None,
+                    SearchTreeIndex::Leaf { leaf_number, .. } => Some(leaf_number)
+                };
+                assert_eq!(leaf_number(hybrid.search(element)), leaf_number(search_tree.search(element)),
+                           "Searching for {} with block height {}", element, block_height);
+            }
+        }
+    }
+
+    #[test]
+    fn index_points_at_lower_bound() {
+        let hybrid = HybridSearchTree::new((0..64).map(|i| i*2), 64, 4).unwrap();
+
+        for element in 0..130 {
+            if let SearchTreeIndex::Leaf { index, leaf_number } = hybrid.search(element) {
+                assert_eq!(hybrid.key_at(index as usize), (element/2).min(63)*2);
+                assert_eq!(leaf_number, (element/2).min(63));
+            } else {
+                panic!("{} is in the tree's span", element);
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::search_tree::SearchTree;
+
+// What to do with a key that is in both merged trees
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    KeepBoth,
+    KeepOne,
+    DropBoth
+}
+
+pub enum DuplicatePolicy {
+    KeepBoth,
+    // Every key once, also the ones repeated within a tree
+    Deduplicate,
+    // Called once per pass over the leaves, so it should only depend on the key
+    Resolve(Box<dyn Fn(i32) -> Resolution>)
+}
+
+// Live leaves of both trees in order, a key in both trees comes as one pair
+fn merged_leaves<'a>(a: &'a SearchTree, b: &'a SearchTree, policy: &'a DuplicatePolicy) -> impl Iterator<Item=i32> + 'a {
+    let mut left = a.leaves().peekable();
+    let mut right = b.leaves().peekable();
+    let pairs = std::iter::from_fn(move || {
+        match (left.peek().copied(), right.peek().copied()) {
+            (Some(l), Some(r)) if l == r => {
+                left.next();
+                right.next();
+                let resolution = match policy {
+                    DuplicatePolicy::KeepBoth => Resolution::KeepBoth,
+                    DuplicatePolicy::Deduplicate => Resolution::KeepOne,
+                    DuplicatePolicy::Resolve(resolve) => resolve(l)
+                };
+                Some(match resolution {
+                    Resolution::KeepBoth => (l, 2),
+                    Resolution::KeepOne => (l, 1),
+                    Resolution::DropBoth => (l, 0)
+                })
+            }
+            (Some(l), Some(r)) if l < r => left.next().map(|l| (l, 1)),
+            (Some(_), None) => left.next().map(|l| (l, 1)),
+            _ => right.next().map(|r| (r, 1))
+        }
+    });
+
+    let deduplicate = matches!(policy, DuplicatePolicy::Deduplicate);
+    let mut last = None;
+    pairs.flat_map(|(key, copies)| std::iter::repeat_n(key, copies))
+        .filter(move |&key| !deduplicate || last.replace(key) != Some(key))
+}
+
+impl SearchTree {
+    //Streams the leaves of both trees into a new one, once to count them and once to lay them out.
+    //Err if the policy drops every leaf.
+    pub fn merge(a: &SearchTree, b: &SearchTree, policy: DuplicatePolicy) -> Result<SearchTree, ()> {
+        let count = merged_leaves(a, b, &policy).count();
+        SearchTree::from_sorted(merged_leaves(a, b, &policy), count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_tree::SearchTree;
+    use crate::search_tree::merge::{DuplicatePolicy, Resolution};
+
+    fn merge(policy: DuplicatePolicy) -> Result<Vec<i32>, ()> {
+        let a = SearchTree::from_sorted((0..30).map(|i| i*2), 30).unwrap();
+        let b = SearchTree::from_sorted((0..15).map(|i| i*3 + 30), 15).unwrap();
+        SearchTree::merge(&a, &b, policy).map(|merged| merged.leaves().collect())
+    }
+
+    #[test]
+    fn merge_with_each_duplicate_policy() {
+        let mut expected: Vec<i32> = (0..30).map(|i| i*2).chain((0..15).map(|i| i*3 + 30)).collect();
+        expected.sort();
+        assert_eq!(merge(DuplicatePolicy::KeepBoth), Ok(expected.clone()), "45 leaves, padded to 64");
+
+        expected.dedup();
+        assert_eq!(merge(DuplicatePolicy::Deduplicate), Ok(expected.clone()));
+
+        let resolve = |key: i32| if key % 4 == 0 { Resolution::KeepOne } else { Resolution::DropBoth };
+        expected.retain(|&key| !(30..60).contains(&key) || key % 6 != 0 || key % 4 == 0);
+        assert_eq!(merge(DuplicatePolicy::Resolve(Box::new(resolve))), Ok(expected));
+    }
+
+    #[test]
+    fn merged_tree_finds_lower_bounds() {
+        let a = SearchTree::from_sorted(vec![1, 1, 5, 9].into_iter(), 4).unwrap();
+        let b = SearchTree::from_sorted(vec![1, 6].into_iter(), 2).unwrap();
+
+        let merged = SearchTree::merge(&a, &b, DuplicatePolicy::Deduplicate).unwrap();
+        assert!(merged.leaves().eq(vec![1, 5, 6, 9]));
+        assert!(merged.contains(6));
+        assert!(!merged.contains(7));
+
+        let dropped = SearchTree::merge(&b, &b, DuplicatePolicy::Resolve(Box::new(|_| Resolution::DropBoth)));
+        assert!(dropped.is_err(), "Every leaf is dropped");
+    }
+}
@@ -2,29 +2,172 @@
 mod search;
 mod create;
 mod util;
+mod hybrid;
+mod deletion;
+mod merge;
+mod set_ops;
+mod split;
+mod finger;
+mod cursor;
+mod oblivious;
+mod range;
+mod blocks;
+mod encrypted;
 
 
-use search::SearchTreeIndex;
-use search::search_for_lower_bound;
-use crate::search_tree::create::layout;
+pub use search::{SearchTreeIndex, Probe, NoProbe};
+#[cfg(test)]
+pub(crate) use search::{Trace, CountingProbe};
+pub use hybrid::{HybridSearchTree, CACHE_LINE_BLOCK_HEIGHT};
+pub use merge::{DuplicatePolicy, Resolution};
+pub use finger::Finger;
+pub use cursor::Cursor;
+pub use range::RangePadding;
+pub use blocks::{KEYS_PER_BLOCK, block_of_node, key_in_block, search_level_by_level};
+pub(crate) use util::{index_of_leaf, index_of_node};
+use search::{search_for_lower_bound, search_for_lower_bound_with_probe, search_sorted_batch_for_lower_bounds};
+use crate::search_tree::create::{layout, update_leaf, PadWithLast};
 use crate::search_tree::search::SearchTreeIndex::{NotInTree};
 use crate::search_tree::search::Leaf;
+use crate::search_tree::deletion::DeletionBitmap;
 
 pub struct SearchTree {
     array: Box<[i32]>,
-    height: u16
+    height: u16,
+    count: usize,
+    deleted: DeletionBitmap
 }
 
 impl SearchTree {
     pub fn search(&self, element: i32) -> SearchTreeIndex {
         return if element >= self.array[0] {
-            let Leaf { index, leaf_number } = search_for_lower_bound(element, self.height, &self.array);
-            SearchTreeIndex::Leaf { index, leaf_number}
+            self.skip_deleted(self.skip_padding(search_for_lower_bound(element, self.height, &self.array)))
         } else {
             NotInTree
         }
     }
 
+    //Reports every index of the underlying array read during the search, e.g. to a cache simulator
+    pub fn search_with_probe<P: Probe>(&self, element: i32, probe: &mut P) -> SearchTreeIndex {
+        probe.touch(0);
+        if element >= self.array[0] {
+            self.skip_deleted(self.skip_padding(search_for_lower_bound_with_probe(element, self.height, &self.array, 0, probe)))
+        } else {
+            NotInTree
+        }
+    }
+
+    //Same results as searching for each element, with the descents of nearby elements shared
+    pub fn search_sorted_batch(&self, elements: &[i32]) -> Vec<SearchTreeIndex> {
+        assert!(elements.windows(2).all(|pair| pair[0] <= pair[1]), "Batch must be sorted");
+
+        let not_in_tree = elements.partition_point(|&element| element < self.array[0]);
+        let leaves = search_sorted_batch_for_lower_bounds(&elements[not_in_tree..], self.height, &self.array);
+        (0..not_in_tree).map(|_| NotInTree)
+            .chain(leaves.into_iter().map(|leaf| self.skip_deleted(self.skip_padding(leaf))))
+            .collect()
+    }
+
+    //Lower bounds in the padding are moved to the last real leaf, which holds the same key
+    fn skip_padding(&self, leaf: Leaf) -> Leaf {
+        if (leaf.leaf_number as usize) < self.count {
+            leaf
+        } else {
+            let leaf_number = self.count as i32 - 1;
+            Leaf { index: index_of_leaf(leaf_number, self.height), leaf_number }
+        }
+    }
+
+    //A deleted lower bound is moved to the nearest live leaf before it
+    fn skip_deleted(&self, leaf: Leaf) -> SearchTreeIndex {
+        match self.deleted.last_live_at_or_before(leaf.leaf_number as usize) {
+            Some(leaf_number) if leaf_number == leaf.leaf_number as usize =>
+                SearchTreeIndex::Leaf { index: leaf.index, leaf_number: leaf.leaf_number },
+            Some(leaf_number) => {
+                let leaf_number = leaf_number as i32;
+                SearchTreeIndex::Leaf { index: index_of_leaf(leaf_number, self.height), leaf_number }
+            }
+            None => NotInTree
+        }
+    }
+
+    pub fn contains(&self, element: i32) -> bool {
+        match self.search(element) {
+            SearchTreeIndex::Leaf { index, .. } => self.array[index as usize] == element,
+            NotInTree => false
+        }
+    }
+
+    //Marks the last live leaf holding `element` as deleted, the layout stays as it is until compact
+    pub fn delete(&mut self, element: i32) -> bool {
+        match self.search(element) {
+            SearchTreeIndex::Leaf { index, leaf_number } if self.array[index as usize] == element => {
+                self.deleted.delete(leaf_number as usize);
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn is_deleted(&self, leaf_number: usize) -> bool {
+        self.deleted.is_deleted(leaf_number)
+    }
+
+    pub fn deleted_count(&self) -> usize {
+        self.deleted.deleted()
+    }
+
+    //Rebuilds the tree from its live leaves once more than `threshold` of them are deleted.
+    //A tree can't be empty, so one whose leaves are all deleted is kept as it is.
+    pub fn compact(&mut self, threshold: f64) -> bool {
+        let live = self.count - self.deleted.deleted();
+        if live == 0 || (self.deleted.deleted() as f64) <= threshold * self.count as f64 {
+            return false
+        }
+
+        let leaves: Vec<i32> = self.leaves().collect();
+        *self = SearchTree::from_sorted(leaves.into_iter(), live).unwrap();
+        true
+    }
+
+    pub fn key_at(&self, index: usize) -> i32 {
+        self.array[index]
+    }
+
+    pub fn leaf(&self, leaf_number: usize) -> i32 {
+        assert!(leaf_number < self.count, "Leaf {} is padding or outside the tree", leaf_number);
+        self.array[index_of_leaf(leaf_number as i32, self.height) as usize]
+    }
+
+    //Live leaves in order, without the padding and the deleted ones
+    pub fn leaves(&self) -> impl Iterator<Item=i32> + '_ {
+        self.live_leaves(0..self.count)
+    }
+
+    fn live_leaves(&self, leaf_numbers: std::ops::Range<usize>) -> impl Iterator<Item=i32> + '_ {
+        leaf_numbers
+            .filter(move |&leaf_number| !self.deleted.is_deleted(leaf_number))
+            .map(move |leaf_number| self.leaf(leaf_number))
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.array.len() * std::mem::size_of::<i32>()
+    }
+
+    //The new key must keep the leaves sorted
+    pub(crate) fn update_leaf(&mut self, leaf_number: usize, key: i32) {
+        assert!(leaf_number < self.count, "Leaf {} is padding or outside the tree", leaf_number);
+        update_leaf(&mut self.array, self.height, leaf_number as i32, key);
+    }
+
     pub fn new<'a>(generator: impl Iterator<Item=i32>, count: usize) -> Result<SearchTree, ()>{
         assert_eq!(count.count_ones(), 1,
                    "Search Tree must be a full binary tree. Number of leaves: {}", count);
@@ -34,9 +177,21 @@ impl SearchTree {
 
         let mut reserved_space = vec![0; size];
         layout(&mut reserved_space, Box::new(generator), height).map(
-            move |_i| SearchTree { array: reserved_space.into_boxed_slice(), height}
+            move |_i| SearchTree { array: reserved_space.into_boxed_slice(), height, count, deleted: DeletionBitmap::new(count) }
         )
     }
+
+    //Any number of sorted leaves. The last leaf is repeated up to the next full binary tree
+    pub fn from_sorted(generator: impl Iterator<Item=i32>, count: usize) -> Result<SearchTree, ()> {
+        if count == 0 {
+            return Err(())
+        }
+
+        let padded_count = count.next_power_of_two();
+        let padded = PadWithLast { generator: generator.take(count), last: None, remaining: padded_count };
+
+        SearchTree::new(padded, padded_count).map(|search_tree| SearchTree { count, ..search_tree })
+    }
 }
 
 
@@ -56,4 +211,76 @@ mod tests {
             "Searching for element in tree's span, but not in tree. Expecting lower bound"
         );
     }
+
+    #[test]
+    fn create_from_sorted_and_search() {
+        let leaves : Vec<i32> = (0..20).map(|i| i*2).collect();
+        let search_tree = SearchTree::from_sorted(leaves.into_iter(), 20).unwrap();
+
+        assert_eq!(search_tree.search(-1), SearchTreeIndex::NotInTree);
+        assert_eq!(search_tree.search(9),
+                   SearchTreeIndex::Leaf{index: 17, leaf_number: 4},
+                   "Searching for element in tree's span, but not in tree. Expecting lower bound");
+        assert_eq!(search_tree.search(38),
+                   SearchTreeIndex::Leaf{index: 41, leaf_number: 19},
+                   "Searching for the last element. Expecting the last leaf, not the padding");
+        assert_eq!(search_tree.search(1000),
+                   SearchTreeIndex::Leaf{index: 41, leaf_number: 19},
+                   "Searching past the last element. Expecting the last leaf, not the padding");
+        assert!(search_tree.leaves().eq((0..20).map(|i| i*2)), "Leaves without the padding");
+    }
+
+    #[test]
+    fn sorted_batch_search_matches_search() {
+        let mut search_tree = SearchTree::from_sorted((0..1500).map(|i| i*4 + (i % 3)), 1500).unwrap();
+        (100..200).for_each(|key| { search_tree.delete(key); });
+
+        let batch: Vec<i32> = (-20..6100).step_by(7).chain(6100..6200).collect();
+        let results = search_tree.search_sorted_batch(&batch);
+        assert_eq!(results.len(), batch.len());
+        for (element, result) in batch.iter().zip(results) {
+            assert_eq!(result, search_tree.search(*element), "Searching for {}", element);
+        }
+        assert!(search_tree.search_sorted_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn delete_and_search_for_live_lower_bound() {
+        let mut search_tree = SearchTree::from_sorted((0..100).map(|i| i*2), 100).unwrap();
+
+        assert!(search_tree.delete(10));
+        assert!(!search_tree.delete(10), "Already deleted");
+        assert!(!search_tree.delete(11), "Not in the tree");
+        (60..=198).step_by(2).for_each(|key| { search_tree.delete(key); });
+
+        assert!(!search_tree.contains(10));
+        assert!(search_tree.contains(12));
+        let lower_bound = |search_tree: &SearchTree, element| match search_tree.search(element) {
+            SearchTreeIndex::Leaf { index, leaf_number } => Some((search_tree.key_at(index as usize), leaf_number)),
+            SearchTreeIndex::NotInTree => None
+        };
+        assert_eq!(lower_bound(&search_tree, 11), Some((8, 4)), "Lower bound is the live leaf before the deleted one");
+        assert_eq!(lower_bound(&search_tree, 1000), Some((58, 29)),
+                   "Lower bound skips the deleted last leaves and the padding");
+        assert_eq!(search_tree.deleted_count(), 71);
+        assert!(search_tree.leaves().eq((0..30).filter(|&i| i != 5).map(|i| i*2)));
+
+        search_tree.delete(0);
+        assert_eq!(search_tree.search(1), SearchTreeIndex::NotInTree, "Every leaf up to 1 is deleted");
+    }
+
+    #[test]
+    fn compact_once_enough_leaves_are_deleted() {
+        let mut search_tree = SearchTree::from_sorted(0..100, 100).unwrap();
+        (0..40).for_each(|key| { search_tree.delete(key*2); });
+
+        assert!(!search_tree.compact(0.5), "40% of the leaves are deleted");
+        assert!(search_tree.compact(0.25));
+        assert_eq!((search_tree.count(), search_tree.deleted_count()), (60, 0));
+        assert!(search_tree.leaves().eq((0..80).filter(|i| i % 2 == 1).chain(80..100)));
+        match search_tree.search(2) {
+            SearchTreeIndex::Leaf { index, leaf_number } => assert_eq!((search_tree.key_at(index as usize), leaf_number), (1, 0)),
+            SearchTreeIndex::NotInTree => panic!("2 is in the compacted tree's span")
+        }
+    }
 }
@@ -0,0 +1,81 @@
+use crate::search_tree::{SearchTree, SearchTreeIndex, Probe, NoProbe};
+use crate::search_tree::util::{index_of_leaf, index_of_node};
+use crate::ct;
+
+impl SearchTree {
+    pub fn search_oblivious(&self, element: i32) -> SearchTreeIndex {
+        self.search_oblivious_with_probe(element, &mut NoProbe)
+    }
+
+    //Same result as search, but the array indices read and the control flow only depend on the shape of the tree.
+    //Every level is read in full, and the child on the path is picked with masks instead of branches,
+    //so a search costs a scan of the whole tree.
+    pub fn search_oblivious_with_probe<P: Probe>(&self, element: i32, probe: &mut P) -> SearchTreeIndex {
+        let (found, leaf_number) = self.lower_bound_oblivious_with_probe(element, probe);
+        let index = index_of_leaf(ct::select(found, leaf_number, 0), self.height);
+
+        //Only the result itself tells whether the element is in the tree's span
+        if found {
+            SearchTreeIndex::Leaf { index, leaf_number }
+        } else {
+            SearchTreeIndex::NotInTree
+        }
+    }
+
+    //Whether there is a live lower bound and its leaf number, both left as values for further constant-time selects
+    pub(crate) fn lower_bound_oblivious_with_probe<P: Probe>(&self, element: i32, probe: &mut P) -> (bool, i32) {
+        probe.touch(0);
+        let in_tree = ct::ge(element, self.array[0]);
+
+        let mut position = 0;
+        for depth in 1..self.height {
+            let right_child = 2*position + 1;
+            let mut right_child_key = 0;
+            for node in 0..1 << depth {
+                let index = index_of_node(depth, node, self.height) as usize;
+                probe.touch(index);
+                right_child_key = ct::select(ct::eq(node, right_child), self.array[index], right_child_key);
+            }
+            position = right_child - 1 + ct::ge(element, right_child_key) as i32;
+        }
+
+        //Lower bounds in the padding or on deleted leaves move to the last live leaf before them
+        let mut leaf_number = -1;
+        for leaf in 0..self.count as i32 {
+            let is_candidate = !self.deleted.is_deleted(leaf as usize) & ct::le(leaf, position);
+            leaf_number = ct::select(is_candidate, leaf, leaf_number);
+        }
+        (in_tree & ct::ge(leaf_number, 0), leaf_number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_tree::{SearchTree, Trace};
+
+    #[test]
+    fn oblivious_search_matches_search() {
+        let mut search_tree = SearchTree::from_sorted((0..300).map(|i| i*3 + 10), 300).unwrap();
+        (200..260).for_each(|key| { search_tree.delete(key); });
+
+        for element in 0..1000 {
+            assert_eq!(search_tree.search_oblivious(element), search_tree.search(element), "Searching for {}", element);
+        }
+    }
+
+    #[test]
+    fn address_trace_is_the_same_for_every_key() {
+        let search_tree = SearchTree::from_sorted((0..100).map(|i| i*2), 100).unwrap();
+        let trace_of = |element| {
+            let mut trace = Trace(Vec::new());
+            search_tree.search_oblivious_with_probe(element, &mut trace);
+            trace.0
+        };
+
+        let expected = trace_of(0);
+        assert_eq!(expected.len(), 2 * 128 - 1, "Every node of the padded tree once");
+        for &element in &[-100, 1, 57, 100, 198, 199, 5000, i32::MIN, i32::MAX] {
+            assert_eq!(trace_of(element), expected, "Trace of {}", element);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::search_tree::{SearchTree, Probe, NoProbe, index_of_leaf};
+
+// How far a range query pads its output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RangePadding {
+    // Up to the next multiple of the bucket size, so only the bucket of the result size leaks
+    Bucket(usize),
+    // Up to the number of leaves, so nothing about the result size leaks
+    WorstCase
+}
+
+impl SearchTree {
+    pub fn range_padded(&self, low: i32, high: i32, padding: RangePadding) -> Vec<Option<i32>> {
+        self.range_padded_with_probe(low, high, padding, &mut NoProbe)
+    }
+
+    //Keys from low to high inclusive, in order among None dummies. The output has the padded size, at most
+    //the number of leaves, and exactly that many consecutive leaves are read whatever the real result size is.
+    //Only the size of the result is hidden, not where it is: the probe sees the searches for both ends,
+    //and the window read starts at the range.
+    pub fn range_padded_with_probe<P: Probe>(&self, low: i32, high: i32, padding: RangePadding, probe: &mut P) -> Vec<Option<i32>> {
+        let start = self.gallop_with_probe(0, low, probe);
+        let end = if high == i32::MAX { self.count } else { self.gallop_with_probe(start, high + 1, probe) };
+        let result_size = end.saturating_sub(start);
+
+        let padded_size = match padding {
+            RangePadding::Bucket(bucket) => {
+                assert!(bucket > 0, "Buckets can't be empty");
+                result_size.div_ceil(bucket).max(1) * bucket
+            }
+            RangePadding::WorstCase => self.count
+        }.min(self.count);
+
+        //The window of leaves read starts at the range, or ends at the last leaf if it's too close to it
+        let first = start.min(self.count - padded_size);
+        (first..first + padded_size).map(|leaf_number| {
+            probe.touch(index_of_leaf(leaf_number as i32, self.height) as usize);
+            let key = self.leaf(leaf_number);
+            let in_range = !self.deleted.is_deleted(leaf_number) & (low <= key) & (key <= high);
+            if in_range { Some(key) } else { None }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::search_tree::{SearchTree, RangePadding, Trace, index_of_leaf};
+
+    #[test]
+    fn padded_ranges_hold_the_keys_in_range() {
+        let mut search_tree = SearchTree::from_sorted((0..200).map(|i| i*3), 200).unwrap();
+        search_tree.delete(30);
+        let keys: BTreeSet<i32> = search_tree.leaves().collect();
+
+        for &(low, high) in &[(0, 0), (10, 40), (-50, 5), (590, 1000), (596, 600), (100, 50), (i32::MIN, i32::MAX)] {
+            for &padding in &[RangePadding::Bucket(1), RangePadding::Bucket(16), RangePadding::WorstCase] {
+                let result = search_tree.range_padded(low, high, padding);
+                let expected: Vec<i32> = keys.iter().copied().filter(|&key| low <= key && key <= high).collect();
+                assert!(result.iter().flatten().copied().eq(expected.iter().copied()), "{}..={} with {:?}", low, high, padding);
+
+                match padding {
+                    RangePadding::Bucket(bucket) => assert!(result.len().is_multiple_of(bucket) || result.len() == 200,
+                                                            "{}..={} with {:?}", low, high, padding),
+                    RangePadding::WorstCase => assert_eq!(result.len(), 200)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_read_only_depend_on_the_padded_size() {
+        let search_tree = SearchTree::from_sorted((0..1000).map(|i| i*2), 1000).unwrap();
+        let leaf_indices: Vec<usize> = (0..1000).map(|leaf_number| index_of_leaf(leaf_number, search_tree.height()) as usize).collect();
+        let leaves_read = |low, high, padding| {
+            let mut trace = Trace(Vec::new());
+            let result = search_tree.range_padded_with_probe(low, high, padding, &mut trace);
+
+            //The window of consecutive leaves comes after the searches for the ends
+            let (searches, window) = trace.0.split_at(trace.0.len() - result.len());
+            assert!(!searches.is_empty(), "The searches are seen too");
+            assert!(leaf_indices.windows(window.len()).any(|leaves| leaves == window), "{}..={}", low, high);
+            window.len()
+        };
+
+        let bucket = RangePadding::Bucket(64);
+        assert_eq!(leaves_read(0, 0, bucket), 64, "A single key");
+        assert_eq!(leaves_read(101, 101, bucket), 64, "No keys");
+        assert_eq!(leaves_read(1000, 1100, bucket), 64, "51 keys");
+        assert_eq!(leaves_read(1990, 5000, bucket), 64, "Range at the end of the tree");
+        assert_eq!(leaves_read(0, 200, bucket), 128, "101 keys");
+        for &(low, high) in &[(0, 0), (101, 101), (0, 5000)] {
+            assert_eq!(leaves_read(low, high, RangePadding::WorstCase), 1000);
+        }
+    }
+
+    #[test]
+    fn trace_shows_where_the_range_is() {
+        let search_tree = SearchTree::from_sorted((0..1000).map(|i| i*2), 1000).unwrap();
+        let trace_of = |low, high| {
+            let mut trace = Trace(Vec::new());
+            search_tree.range_padded_with_probe(low, high, RangePadding::Bucket(64), &mut trace);
+            trace.0
+        };
+
+        assert_ne!(trace_of(0, 10), trace_of(1000, 1010), "Same size, different place");
+    }
+}
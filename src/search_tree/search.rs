@@ -1,5 +1,7 @@
-use crate::search_tree::util::{is_odd, size_of_tree_with_height, number_of_leaves_in_tree};
+use crate::search_tree::util::{is_odd, size_of_tree_with_height, number_of_leaves_in_tree,
+                                size_of_hybrid_tree_with_height};
 
+use crate::ct;
 use core_simd::*;
 
 #[derive(Eq, PartialEq, Debug)]
@@ -27,25 +29,82 @@ fn search_3_level_tree_for_lower_bound_simd(of: i32, array: &[i32]) -> Leaf {
 }
 
 fn search_2_level_tree_for_lower_bound(of: i32, array: &[i32]) -> Leaf {
-   let lower_bound_is_2nd_leaf = (of >= array[2]) as i32;
+   let lower_bound_is_2nd_leaf = ct::ge(of, array[2]) as i32;
 
    Leaf { index: 1 + lower_bound_is_2nd_leaf, leaf_number: 0 + lower_bound_is_2nd_leaf }
 }
 
+// Counts the leaves less than or equal to `of`, four at a time
+fn search_flat_block_for_lower_bound(of: i32, array: &[i32]) -> Leaf {
+    const ONE: Simd<i32, 4> = i32x4::splat(1);
+    const ZERO: Simd<i32, 4> = i32x4::splat(0);
+
+    let of_simd = i32x4::splat(of);
+    let chunks = array.chunks_exact(4);
+    let remainder = chunks.remainder().iter().filter(|&&leaf| leaf <= of).count() as i32;
+
+    let lower_bounds = chunks.fold(ZERO, |count, chunk|
+        count + of_simd.lanes_ge(i32x4::from_slice(chunk)).select(ONE, ZERO)
+    ).horizontal_sum() + remainder;
+
+    Leaf { index: lower_bounds - 1, leaf_number: lower_bounds - 1 }
+}
+
 fn search_single_node_tree_for_lower_bound(_of: i32, _array: &[i32]) -> Leaf {
     Leaf {index: 0, leaf_number: 0}
 }
 
-pub fn search_for_lower_bound_in_top_subtree(element: i32, height: u16, array: &[i32]) -> i32 {
+pub trait Probe {
+    fn touch(&mut self, index: usize);
+}
+
+pub struct NoProbe;
+
+impl Probe for NoProbe {
+    #[inline(always)]
+    fn touch(&mut self, _index: usize) {}
+}
+
+// Probes for tests: every index touched, in order
+#[cfg(test)]
+pub struct Trace(pub Vec<usize>);
+
+#[cfg(test)]
+impl Probe for Trace {
+    fn touch(&mut self, index: usize) {
+        self.0.push(index);
+    }
+}
+
+// Only how many indices were touched
+#[cfg(test)]
+pub struct CountingProbe(pub usize);
+
+#[cfg(test)]
+impl Probe for CountingProbe {
+    fn touch(&mut self, _index: usize) {
+        self.0 += 1;
+    }
+}
+
+pub fn search_for_lower_bound_in_top_subtree<P: Probe>(
+    element: i32,
+    height: u16,
+    array: &[i32],
+    offset: usize,
+    probe: &mut P
+) -> i32 {
     let top_subtree_is_taller = is_odd(height);
     let subtree_height = height >> 1;
     let top_subtree_height = subtree_height + top_subtree_is_taller as u16;
     let top_subtree_size = size_of_tree_with_height(top_subtree_height);
     let bottom_subtree_size = size_of_tree_with_height(subtree_height);
 
-    let  Leaf { index: _, leaf_number } = search_for_lower_bound(element, top_subtree_height, &array);
+    let  Leaf { index: _, leaf_number } =
+        search_for_lower_bound_with_probe(element, top_subtree_height, &array, offset, probe);
 
     let right_subtree_root_index = (top_subtree_size + bottom_subtree_size*(2*leaf_number+1)) as usize;
+    probe.touch(offset + right_subtree_root_index);
     let right_subtree_root = array[right_subtree_root_index];
 
     let is_right_subtree = element >= right_subtree_root;
@@ -66,20 +125,40 @@ pub fn subtree_root_index_generator(height: u16) -> impl Fn(i32) -> i32 {
 
 //Lower bound must exist
 pub fn search_for_lower_bound(element: i32, height: u16, array: &[i32]) -> Leaf {
+    search_for_lower_bound_with_probe(element, height, array, 0, &mut NoProbe)
+}
+
+//Same as search_for_lower_bound, but reports every array index read to the probe.
+//offset is the position of array within the whole tree
+pub fn search_for_lower_bound_with_probe<P: Probe>(
+    element: i32,
+    height: u16,
+    array: &[i32],
+    offset: usize,
+    probe: &mut P
+) -> Leaf {
     return match height {
-        3 => { search_3_level_tree_for_lower_bound_simd(element, array) }
-        2 => { search_2_level_tree_for_lower_bound(element, array) }
+        3 => {
+            (3..=6).for_each(|i| probe.touch(offset + i));
+            search_3_level_tree_for_lower_bound_simd(element, array)
+        }
+        2 => {
+            probe.touch(offset + 2);
+            search_2_level_tree_for_lower_bound(element, array)
+        }
         1 => { search_single_node_tree_for_lower_bound(element, array) }
         _ => {
            let subtree_height = height >> 1;
            let subtree_root_index = subtree_root_index_generator(height);
 
-           let subtree_number = search_for_lower_bound_in_top_subtree(element, height, array);
+           let subtree_number = search_for_lower_bound_in_top_subtree(element, height, array, offset, probe);
 
             let bottom_subtree_index = {
                 let start_index = subtree_root_index(subtree_number) as usize;
                 let end_index = subtree_root_index(subtree_number+1) as usize;
-                search_for_lower_bound(element, subtree_height, &array[start_index..end_index])
+                search_for_lower_bound_with_probe(
+                    element, subtree_height, &array[start_index..end_index], offset + start_index, probe
+                )
             };
 
             let Leaf { index: index_in_subtree, leaf_number: leaf_number_in_subtree } = bottom_subtree_index;
@@ -91,12 +170,91 @@ pub fn search_for_lower_bound(element: i32, height: u16, array: &[i32]) -> Leaf
     }
 }
 
+//Lower bounds of a sorted batch, which must all exist. The top subtree is searched for the whole batch,
+//which then splits into runs of elements going to the same bottom subtree, and each run descends it once.
+pub fn search_sorted_batch_for_lower_bounds(elements: &[i32], height: u16, array: &[i32]) -> Vec<Leaf> {
+    if height <= 3 {
+        return elements.iter().map(|&element| search_for_lower_bound(element, height, array)).collect()
+    }
+
+    let subtree_height = height >> 1;
+    let top_subtree_height = height - subtree_height;
+    let leaves_in_subtree = number_of_leaves_in_tree(subtree_height);
+    let subtree_root_index = subtree_root_index_generator(height);
+
+    let top_leaves = search_sorted_batch_for_lower_bounds(elements, top_subtree_height, array);
+    let subtree_numbers: Vec<i32> = elements.iter().zip(top_leaves).map(|(&element, Leaf { leaf_number, .. })| {
+        let right_subtree_root_index = subtree_root_index(2*leaf_number + 1) as usize;
+        2*leaf_number + (element >= array[right_subtree_root_index]) as i32
+    }).collect();
+
+    let mut leaves = Vec::with_capacity(elements.len());
+    let mut start = 0;
+    while start < elements.len() {
+        let subtree_number = subtree_numbers[start];
+        let end = start + subtree_numbers[start..].iter().take_while(|&&number| number == subtree_number).count();
+        let start_index = subtree_root_index(subtree_number);
+        let end_index = subtree_root_index(subtree_number + 1);
+
+        let group = search_sorted_batch_for_lower_bounds(
+            &elements[start..end], subtree_height, &array[start_index as usize..end_index as usize]
+        );
+        leaves.extend(group.into_iter().map(|Leaf { index, leaf_number }| Leaf {
+            index: start_index + index,
+            leaf_number: leaves_in_subtree * subtree_number + leaf_number
+        }));
+        start = end;
+    }
+    leaves
+}
+
+//Lower bound must exist. Tree laid out by create::layout_hybrid with the same block_height
+pub fn search_for_lower_bound_hybrid<P: Probe>(
+    element: i32,
+    height: u16,
+    block_height: u16,
+    array: &[i32],
+    offset: usize,
+    probe: &mut P
+) -> Leaf {
+    if height <= block_height {
+        let number_of_leaves = number_of_leaves_in_tree(height) as usize;
+        (0..number_of_leaves).for_each(|i| probe.touch(offset + i));
+        return search_flat_block_for_lower_bound(element, &array[..number_of_leaves])
+    }
+
+    let subtree_height = height >> 1;
+    let top_subtree_height = height - subtree_height;
+    let top_subtree_size = size_of_hybrid_tree_with_height(top_subtree_height, block_height);
+    let bottom_subtree_size = size_of_hybrid_tree_with_height(subtree_height, block_height);
+
+    let Leaf { index: _, leaf_number } =
+        search_for_lower_bound_hybrid(element, top_subtree_height, block_height, array, offset, probe);
+
+    let right_subtree_root_index = (top_subtree_size + bottom_subtree_size*(2*leaf_number+1)) as usize;
+    probe.touch(offset + right_subtree_root_index);
+    let is_right_subtree = element >= array[right_subtree_root_index];
+    let subtree_number = 2*leaf_number + is_right_subtree as i32;
+
+    let start_index = (top_subtree_size + bottom_subtree_size*subtree_number) as usize;
+    let end_index = start_index + bottom_subtree_size as usize;
+    let Leaf { index: index_in_subtree, leaf_number: leaf_number_in_subtree } = search_for_lower_bound_hybrid(
+        element, subtree_height, block_height, &array[start_index..end_index], offset + start_index, probe
+    );
+
+    Leaf {
+        index: start_index as i32 + index_in_subtree,
+        leaf_number: number_of_leaves_in_tree(subtree_height) * subtree_number + leaf_number_in_subtree
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::search_tree::search::{search_single_node_tree_for_lower_bound,
                                      search_for_lower_bound,
                                      search_3_level_tree_for_lower_bound_simd, Leaf,
-                                     search_2_level_tree_for_lower_bound};
+                                     search_2_level_tree_for_lower_bound,
+                                     search_flat_block_for_lower_bound, search_for_lower_bound_hybrid, NoProbe};
 
     #[test]
     fn search_in_base_case_height3() {
@@ -184,4 +342,33 @@ mod tests {
         test_case(58,11,5, "element in range but not in tree");
         test_case(800,14,7, "element greater than tree");
     }
-}
+
+    #[test]
+    fn search_in_flat_block() {
+        let block = [1,3,5,7,  9,11];
+
+        let test_case = | of: i32, expected_leaf: i32, on_fail: &str |
+            assert_eq!(search_flat_block_for_lower_bound(of, &block),
+                       Leaf { index: expected_leaf, leaf_number: expected_leaf },
+                       "{}", on_fail);
+
+        test_case(1,0, "Search for smallest element of block");
+        test_case(8,3, "Search for element not in block within range");
+        test_case(9,4, "Search for element in remainder of block");
+        test_case(200,5, "Search for element greater than block");
+    }
+
+    #[test]
+    fn test_search_hybrid_tree() {
+        let tree = [1,5,  1,2,  3,4,  5,57,  77,78];
+
+        let test_case = | of: i32, expected_index: i32, expected_leaf: i32, on_fail: &str |
+            assert_eq!(search_for_lower_bound_hybrid(of, 4, 2, &tree, 0, &mut NoProbe),
+                       Leaf { index: expected_index, leaf_number: expected_leaf },
+                       "{}", on_fail);
+
+        test_case(1,2,0, "searching for the smallest element");
+        test_case(58,7,5, "element in range but not in tree");
+        test_case(800,9,7, "element greater than tree");
+    }
+}
\ No newline at end of file
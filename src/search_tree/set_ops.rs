@@ -0,0 +1,139 @@
+use crate::search_tree::{SearchTree, Probe, NoProbe};
+
+// Each key once
+fn distinct(keys: impl Iterator<Item=i32>) -> impl Iterator<Item=i32> {
+    let mut last = None;
+    keys.filter(move |&key| last.replace(key) != Some(key))
+}
+
+fn merge_sorted(a: impl Iterator<Item=i32>, b: impl Iterator<Item=i32>) -> impl Iterator<Item=i32> {
+    let mut a = a.peekable();
+    let mut b = b.peekable();
+    std::iter::from_fn(move || match (a.peek(), b.peek()) {
+        (Some(x), Some(y)) if x <= y => a.next(),
+        (Some(_), None) => a.next(),
+        _ => b.next()
+    })
+}
+
+impl SearchTree {
+    //First leaf from `finger` on that is at least `key`, or the count. That's the leaf after the lower bound
+    //of `key - 1`, found with the finger search, so the cost is logarithmic in the distance instead of the tree size.
+    pub(super) fn gallop(&self, finger: usize, key: i32) -> usize {
+        self.gallop_with_probe(finger, key, &mut NoProbe)
+    }
+
+    pub(super) fn gallop_with_probe<P: Probe>(&self, finger: usize, key: i32, probe: &mut P) -> usize {
+        if key == i32::MIN {
+            return finger
+        }
+        self.lower_bound_from_with_probe(finger as i32, key - 1, probe)
+            .map_or(0, |leaf| leaf.leaf_number as usize + 1)
+            .max(finger)
+    }
+
+    fn has_live_leaf_at(&self, leaf_number: usize, key: i32) -> bool {
+        (leaf_number..self.count)
+            .take_while(|&leaf_number| self.leaf(leaf_number) == key)
+            .any(|leaf_number| !self.deleted.is_deleted(leaf_number))
+    }
+
+    // Keys of `self` that are, or aren't, in `other`, galloping forward through `other`
+    fn filter_by<'a>(&'a self, other: &'a SearchTree, keep_if_in_other: bool) -> impl Iterator<Item=i32> + 'a {
+        let mut finger = 0;
+        distinct(self.leaves()).filter(move |&key| {
+            finger = other.gallop(finger, key);
+            other.has_live_leaf_at(finger, key) == keep_if_in_other
+        })
+    }
+
+    //The set operations treat the live leaves as a set, so a repeated key counts once.
+    //Their results are sorted and can be built into a tree with from_sorted_iter.
+    pub fn intersection<'a>(&'a self, other: &'a SearchTree) -> impl Iterator<Item=i32> + 'a {
+        //The smaller tree drives, so the cost is about |smaller| * log(|larger| / |smaller|)
+        let (smaller, larger) = if self.count <= other.count { (self, other) } else { (other, self) };
+        smaller.filter_by(larger, true)
+    }
+
+    pub fn difference<'a>(&'a self, other: &'a SearchTree) -> impl Iterator<Item=i32> + 'a {
+        self.filter_by(other, false)
+    }
+
+    pub fn union<'a>(&'a self, other: &'a SearchTree) -> impl Iterator<Item=i32> + 'a {
+        distinct(merge_sorted(self.leaves(), other.leaves()))
+    }
+
+    pub fn symmetric_difference<'a>(&'a self, other: &'a SearchTree) -> impl Iterator<Item=i32> + 'a {
+        merge_sorted(self.difference(other), other.difference(self))
+    }
+
+    pub fn from_sorted_iter(keys: impl Iterator<Item=i32>) -> Result<SearchTree, ()> {
+        let keys: Vec<i32> = keys.collect();
+        SearchTree::from_sorted(keys.iter().copied(), keys.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use crate::search_tree::SearchTree;
+
+    fn random_tree(rng: &mut StdRng, count: usize, range: i32) -> (SearchTree, BTreeSet<i32>) {
+        let mut keys: Vec<i32> = (0..count).map(|_| rng.gen_range(0..range)).collect();
+        keys.sort();
+        let mut tree = SearchTree::from_sorted(keys.iter().copied(), keys.len()).unwrap();
+
+        let mut set: BTreeSet<i32> = keys.into_iter().collect();
+        for _ in 0..count / 10 {
+            let key = rng.gen_range(0..range);
+            while tree.delete(key) {}
+            set.remove(&key);
+        }
+        (tree, set)
+    }
+
+    #[test]
+    fn set_operations_match_btreeset() {
+        let mut rng = StdRng::seed_from_u64(36);
+        for &(count_a, count_b) in &[(1, 1), (10, 1000), (1000, 10), (500, 700), (3000, 3000)] {
+            let (a, set_a) = random_tree(&mut rng, count_a, 5000);
+            let (b, set_b) = random_tree(&mut rng, count_b, 5000);
+
+            assert!(a.intersection(&b).eq(set_a.intersection(&set_b).copied()), "{} and {} keys", count_a, count_b);
+            assert!(a.union(&b).eq(set_a.union(&set_b).copied()), "{} and {} keys", count_a, count_b);
+            assert!(a.difference(&b).eq(set_a.difference(&set_b).copied()), "{} and {} keys", count_a, count_b);
+            assert!(a.symmetric_difference(&b).eq(set_a.symmetric_difference(&set_b).copied()),
+                    "{} and {} keys", count_a, count_b);
+        }
+    }
+
+    #[test]
+    fn gallop_finds_the_first_leaf_at_least_the_key() {
+        let mut rng = StdRng::seed_from_u64(360);
+        let (tree, _) = random_tree(&mut rng, 2000, 3000);
+
+        for key in (-2..3002).chain(vec![i32::MIN, i32::MAX]) {
+            let expected = (0..tree.count).find(|&leaf_number| tree.leaf(leaf_number) >= key).unwrap_or(tree.count);
+            for &finger in &[0, expected / 2, expected.saturating_sub(1), expected] {
+                assert_eq!(tree.gallop(finger, key), expected, "{} from leaf {}", key, finger);
+            }
+        }
+    }
+
+    #[test]
+    fn build_tree_from_intersection() {
+        let a = SearchTree::from_sorted((0..100).map(|i| i*2), 100).unwrap();
+        let b = SearchTree::from_sorted((0..100).map(|i| i*3), 100).unwrap();
+
+        let both = SearchTree::from_sorted_iter(a.intersection(&b)).unwrap();
+        assert!(both.leaves().eq((0..34).map(|i| i*6)));
+        assert!(both.contains(48));
+        assert!(!both.contains(50));
+
+        let none = SearchTree::from_sorted_iter(a.difference(&a));
+        assert!(none.is_err(), "Empty result");
+    }
+}
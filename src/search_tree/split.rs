@@ -0,0 +1,73 @@
+use crate::search_tree::SearchTree;
+
+impl SearchTree {
+    //Keys less than `key` go left, the others right. Both sides are laid out from the live leaves in order,
+    //and Err if one of them would be empty.
+    pub fn split_at(&self, key: i32) -> Result<(SearchTree, SearchTree), ()> {
+        let split = self.gallop(0, key);
+        let live = |leaf_numbers: std::ops::Range<usize>| leaf_numbers.filter(|&n| !self.deleted.is_deleted(n)).count();
+
+        let left = SearchTree::from_sorted(self.live_leaves(0..split), live(0..split))?;
+        let right = SearchTree::from_sorted(self.live_leaves(split..self.count), live(split..self.count))?;
+        Ok((left, right))
+    }
+
+    //Err unless every live key of `left` is less than every live key of `right`
+    pub fn concat(left: &SearchTree, right: &SearchTree) -> Result<SearchTree, ()> {
+        if let (Some(last), Some(first)) = (left.leaves().last(), right.leaves().next()) {
+            if last >= first {
+                return Err(())
+            }
+        }
+
+        let count = left.count - left.deleted_count() + right.count - right.deleted_count();
+        SearchTree::from_sorted(left.leaves().chain(right.leaves()), count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search_tree::SearchTree;
+
+    #[test]
+    fn split_and_concat_back() {
+        let search_tree = SearchTree::from_sorted((0..300).map(|i| i*3), 300).unwrap();
+
+        for &key in &[1, 3, 100, 450, 897] {
+            let (left, right) = search_tree.split_at(key).unwrap();
+            assert!(left.leaves().all(|leaf| leaf < key), "Split at {}", key);
+            assert!(right.leaves().all(|leaf| leaf >= key), "Split at {}", key);
+            assert_eq!(left.count() + right.count(), 300);
+
+            let concatenated = SearchTree::concat(&left, &right).unwrap();
+            assert!(concatenated.leaves().eq(search_tree.leaves()), "Split at {}", key);
+        }
+
+        assert!(search_tree.split_at(0).is_err(), "Nothing goes left");
+        assert!(search_tree.split_at(898).is_err(), "Nothing goes right");
+    }
+
+    #[test]
+    fn split_skips_deleted_leaves() {
+        let mut search_tree = SearchTree::from_sorted(0..20, 20).unwrap();
+        (5..10).for_each(|key| { search_tree.delete(key); });
+
+        let (left, right) = search_tree.split_at(8).unwrap();
+        assert!(left.leaves().eq(0..5));
+        assert!(right.leaves().eq(10..20));
+        assert_eq!((left.count(), right.count()), (5, 10));
+    }
+
+    #[test]
+    fn concat_needs_disjoint_ordered_trees() {
+        let low = SearchTree::from_sorted(0..10, 10).unwrap();
+        let high = SearchTree::from_sorted(9..30, 21).unwrap();
+        assert!(SearchTree::concat(&low, &high).is_err(), "9 is in both trees");
+        assert!(SearchTree::concat(&high, &low).is_err(), "Wrong order");
+
+        let high = SearchTree::from_sorted(10..30, 20).unwrap();
+        let concatenated = SearchTree::concat(&low, &high).unwrap();
+        assert!(concatenated.leaves().eq(0..30));
+        assert!(concatenated.contains(29));
+    }
+}
@@ -12,9 +12,77 @@ pub fn number_of_leaves_in_tree(height: u16) -> i32 {
     1 << (height - 1)
 }
 
+pub fn index_of_leaf(leaf_number: i32, height: u16) -> i32 {
+    match height {
+        1 => { 0 }
+        2 => { 1 + leaf_number }
+        3 => { 3 + leaf_number }
+        _ => {
+            let subtree_height = height >> 1;
+            let top_subtree_height = height - subtree_height;
+            let leaves_in_subtree = number_of_leaves_in_tree(subtree_height);
+            let subtree_number = leaf_number / leaves_in_subtree;
+
+            size_of_tree_with_height(top_subtree_height)
+                + size_of_tree_with_height(subtree_height) * subtree_number
+                + index_of_leaf(leaf_number % leaves_in_subtree, subtree_height)
+        }
+    }
+}
+
+// Node at `position` among the 2^depth nodes at `depth`, counted from the root at depth 0
+pub fn index_of_node(depth: u16, position: i32, height: u16) -> i32 {
+    if height == 1 {
+        return 0
+    }
+
+    let subtree_height = height >> 1;
+    let top_subtree_height = height - subtree_height;
+    if depth < top_subtree_height {
+        index_of_node(depth, position, top_subtree_height)
+    } else {
+        let depth_in_subtree = depth - top_subtree_height;
+        let subtree_number = position >> depth_in_subtree;
+
+        size_of_tree_with_height(top_subtree_height)
+            + size_of_tree_with_height(subtree_height) * subtree_number
+            + index_of_node(depth_in_subtree, position & ((1 << depth_in_subtree) - 1), subtree_height)
+    }
+}
+
+// A hybrid tree stops the recursion at block_height and only keeps the leaves of those blocks
+pub fn size_of_hybrid_tree_with_height(height: u16, block_height: u16) -> i32 {
+    if height <= block_height {
+        number_of_leaves_in_tree(height)
+    } else {
+        let subtree_height = height >> 1;
+        let top_subtree_height = height - subtree_height;
+
+        size_of_hybrid_tree_with_height(top_subtree_height, block_height)
+            + 2 * number_of_leaves_in_tree(top_subtree_height)
+                * size_of_hybrid_tree_with_height(subtree_height, block_height)
+    }
+}
+
+pub fn index_of_leaf_in_hybrid_tree(leaf_number: i32, height: u16, block_height: u16) -> i32 {
+    if height <= block_height {
+        leaf_number
+    } else {
+        let subtree_height = height >> 1;
+        let top_subtree_height = height - subtree_height;
+        let leaves_in_subtree = number_of_leaves_in_tree(subtree_height);
+        let subtree_number = leaf_number / leaves_in_subtree;
+
+        size_of_hybrid_tree_with_height(top_subtree_height, block_height)
+            + size_of_hybrid_tree_with_height(subtree_height, block_height) * subtree_number
+            + index_of_leaf_in_hybrid_tree(leaf_number % leaves_in_subtree, subtree_height, block_height)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::search_tree::util::{is_odd};
+    use crate::search_tree::util::{is_odd, index_of_leaf, index_of_node, size_of_tree_with_height,
+                                   size_of_hybrid_tree_with_height, index_of_leaf_in_hybrid_tree};
 
     #[test]
     fn test_is_odd_helper() {
@@ -22,5 +90,53 @@ mod tests {
         assert_eq!(is_odd(524), false, "Test is_odd on an even number");
     }
 
+    #[test]
+    fn test_index_of_leaf_helper() {
+        let tree_of_height_4 = [0,0,4,  0,0,1,  2,2,3,  4,4,5,  6,6,7];
+
+        for leaf_number in 0..8 {
+            assert_eq!(tree_of_height_4[index_of_leaf(leaf_number, 4) as usize], leaf_number,
+                       "Leaf {} of tree with height 4", leaf_number);
+        }
+    }
+
+    #[test]
+    fn test_index_of_node_helper() {
+        let tree_of_height_4 = [0,0,4,  0,0,1,  2,2,3,  4,4,5,  6,6,7];
+
+        assert_eq!(index_of_node(0, 0, 4), 0, "Root");
+        assert_eq!(index_of_node(1, 1, 4), 2, "Right child of the root");
+        assert_eq!(index_of_node(2, 2, 4), 9, "Root of the third bottom subtree");
+        for leaf_number in 0..8 {
+            assert_eq!(index_of_node(3, leaf_number, 4), index_of_leaf(leaf_number, 4));
+            assert_eq!(tree_of_height_4[index_of_node(3, leaf_number, 4) as usize], leaf_number);
+        }
+        for height in 1..12 {
+            let mut indices: Vec<i32> = (0..height)
+                .flat_map(|depth| (0..1 << depth).map(move |position| index_of_node(depth, position, height)))
+                .collect();
+            indices.sort();
+            assert!(indices.into_iter().eq(0..size_of_tree_with_height(height)), "Every node of height {}", height);
+        }
+    }
+
+    #[test]
+    fn test_size_of_hybrid_tree_helper() {
+        assert_eq!(size_of_hybrid_tree_with_height(4, 2), 10, "Top block of 2 leaves and 4 blocks of 2 leaves");
+        assert_eq!(size_of_hybrid_tree_with_height(5, 5), 16, "A single block keeps only the leaves");
+        assert_eq!(size_of_hybrid_tree_with_height(7, 1), size_of_tree_with_height(7),
+                   "Blocks of a single leaf are the plain layout");
+    }
+
+    #[test]
+    fn test_index_of_leaf_in_hybrid_tree_helper() {
+        let tree_of_height_4 = [0,4,  0,1,  2,3,  4,5,  6,7];
+
+        for leaf_number in 0..8 {
+            assert_eq!(tree_of_height_4[index_of_leaf_in_hybrid_tree(leaf_number, 4, 2) as usize], leaf_number,
+                       "Leaf {} of hybrid tree with height 4", leaf_number);
+        }
+    }
+
 
 }
\ No newline at end of file
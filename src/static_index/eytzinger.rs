@@ -0,0 +1,69 @@
+use crate::static_index::StaticIndex;
+
+// Keys in breadth first order of a full binary search tree, root at index 1.
+// The tree is padded with i32::MAX, so the sorted position of a node follows from its index.
+pub struct Eytzinger {
+    array: Box<[i32]>,
+    height: u32,
+    count: usize,
+}
+
+impl Eytzinger {
+    fn fill(array: &mut [i32], keys: &mut impl Iterator<Item=i32>, index: usize) {
+        if index < array.len() {
+            Eytzinger::fill(array, keys, 2*index);
+            array[index] = keys.next().unwrap_or(i32::MAX);
+            Eytzinger::fill(array, keys, 2*index + 1);
+        }
+    }
+
+    fn sorted_position(&self, index: usize) -> usize {
+        let depth = usize::BITS - 1 - index.leading_zeros();
+        let position_in_level = index - (1 << depth);
+        ((2*position_in_level + 1) << (self.height - 1 - depth)) - 1
+    }
+
+    // Sorted position of the greatest key less than or equal to `key`
+    fn search(&self, key: i32) -> Option<usize> {
+        let mut index = 1;
+        let mut lower_bound = 0;
+        while index < self.array.len() {
+            let go_right = self.array[index] <= key;
+            lower_bound = if go_right { index } else { lower_bound };
+            index = 2*index + go_right as usize;
+        }
+
+        //Padding is only a lower bound of i32::MAX, whose real lower bound is the last key
+        (lower_bound > 0).then(|| self.sorted_position(lower_bound).min(self.count - 1))
+    }
+}
+
+impl StaticIndex for Eytzinger {
+    fn build(sorted_keys: &[i32]) -> Result<Self, ()> {
+        if sorted_keys.is_empty() {
+            return Err(())
+        }
+
+        let height = usize::BITS - sorted_keys.len().leading_zeros();
+        let mut array = vec![0; 1 << height];
+        Eytzinger::fill(&mut array, &mut sorted_keys.iter().copied(), 1);
+
+        Ok(Eytzinger { array: array.into_boxed_slice(), height, count: sorted_keys.len() })
+    }
+
+    fn lower_bound(&self, key: i32) -> Option<i32> {
+        self.search(key).map(|position| {
+            let depth = self.height - 1 - (position + 1).trailing_zeros();
+            let position_in_level = (position + 1) >> (self.height - depth);
+            self.array[(1 << depth) + position_in_level]
+        })
+    }
+
+    fn rank(&self, key: i32) -> usize {
+        self.search(key).map_or(0, |position| position + 1)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.array.len() * std::mem::size_of::<i32>()
+    }
+}
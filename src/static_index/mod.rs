@@ -0,0 +1,141 @@
+mod eytzinger;
+mod s_tree;
+mod sorted_array;
+
+use crate::search_tree::{SearchTree, SearchTreeIndex, HybridSearchTree, CACHE_LINE_BLOCK_HEIGHT};
+
+pub use eytzinger::Eytzinger;
+pub use s_tree::STree;
+pub use sorted_array::SortedArray;
+
+// Common interface of the static search layouts, so they can be compared on the same data.
+// Like SearchTree::search, `lower_bound` is the greatest key less than or equal to the searched one.
+pub trait StaticIndex: Sized {
+    fn build(sorted_keys: &[i32]) -> Result<Self, ()>;
+
+    fn lower_bound(&self, key: i32) -> Option<i32>;
+
+    // Number of keys less than or equal to `key`
+    fn rank(&self, key: i32) -> usize;
+
+    fn size_in_bytes(&self) -> usize;
+}
+
+impl StaticIndex for SearchTree {
+    fn build(sorted_keys: &[i32]) -> Result<Self, ()> {
+        SearchTree::from_sorted(sorted_keys.iter().copied(), sorted_keys.len())
+    }
+
+    fn lower_bound(&self, key: i32) -> Option<i32> {
+        match self.search(key) {
+            SearchTreeIndex::NotInTree => None,
+            SearchTreeIndex::Leaf { index, .. } => Some(self.key_at(index as usize))
+        }
+    }
+
+    fn rank(&self, key: i32) -> usize {
+        match self.search(key) {
+            SearchTreeIndex::NotInTree => 0,
+            SearchTreeIndex::Leaf { leaf_number, .. } => leaf_number as usize + 1
+        }
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        SearchTree::size_in_bytes(self)
+    }
+}
+
+impl StaticIndex for HybridSearchTree {
+    fn build(sorted_keys: &[i32]) -> Result<Self, ()> {
+        HybridSearchTree::from_sorted(sorted_keys.iter().copied(), sorted_keys.len(), CACHE_LINE_BLOCK_HEIGHT)
+    }
+
+    fn lower_bound(&self, key: i32) -> Option<i32> {
+        match self.search(key) {
+            SearchTreeIndex::NotInTree => None,
+            SearchTreeIndex::Leaf { index, .. } => Some(self.key_at(index as usize))
+        }
+    }
+
+    fn rank(&self, key: i32) -> usize {
+        match self.search(key) {
+            SearchTreeIndex::NotInTree => 0,
+            SearchTreeIndex::Leaf { leaf_number, .. } => leaf_number as usize + 1
+        }
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        HybridSearchTree::size_in_bytes(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::static_index::{StaticIndex, Eytzinger, STree, SortedArray};
+    use crate::search_tree::{SearchTree, HybridSearchTree};
+
+    fn check_against_sorted_keys<I: StaticIndex>(keys: &[i32]) {
+        let index = I::build(keys).unwrap();
+
+        let first = keys.first().copied().unwrap_or(0);
+        let last = keys.last().copied().unwrap_or(0);
+        for key in (first - 3)..=(last + 3) {
+            let rank = keys.partition_point(|&k| k <= key);
+            assert_eq!(index.rank(key), rank, "rank of {} in {:?}", key, keys);
+            assert_eq!(index.lower_bound(key), rank.checked_sub(1).map(|i| keys[i]),
+                       "lower bound of {} in {:?}", key, keys);
+        }
+    }
+
+    fn check_layout<I: StaticIndex>() {
+        for count in &[1, 2, 3, 7, 16, 17, 100, 289, 1000] {
+            let keys: Vec<i32> = (0..*count).map(|i| i*3 - 50).collect();
+            check_against_sorted_keys::<I>(&keys);
+        }
+
+        check_against_sorted_keys::<I>(&[1, 1, 1, 4, 4, 9, 9, 9, 9, 12]);
+    }
+
+    #[test]
+    fn van_emde_boas_layout() {
+        check_layout::<SearchTree>();
+        assert!(SearchTree::build(&[]).is_err(), "A search tree can't be empty");
+    }
+
+    #[test]
+    fn hybrid_van_emde_boas_layout() {
+        check_layout::<HybridSearchTree>();
+    }
+
+    #[test]
+    fn eytzinger_layout() {
+        check_layout::<Eytzinger>();
+    }
+
+    #[test]
+    fn s_tree_layout() {
+        check_layout::<STree>();
+    }
+
+    #[test]
+    fn sorted_array_layout() {
+        check_layout::<SortedArray>();
+    }
+
+    #[test]
+    fn keys_at_the_edges_of_i32() {
+        fn check<I: StaticIndex>() {
+            let index = I::build(&[i32::MIN, 5, i32::MAX]).unwrap();
+            assert_eq!(index.lower_bound(i32::MIN), Some(i32::MIN));
+            assert_eq!(index.lower_bound(i32::MAX), Some(i32::MAX));
+            assert_eq!(index.rank(i32::MAX), 3);
+            assert_eq!(index.rank(i32::MAX - 1), 2);
+        }
+
+        check::<SearchTree>();
+        check::<HybridSearchTree>();
+        check::<Eytzinger>();
+        check::<STree>();
+        check::<SortedArray>();
+    }
+}
@@ -0,0 +1,95 @@
+use core_simd::*;
+
+use crate::static_index::StaticIndex;
+
+const B: usize = 16;
+
+type Node = [i32; B];
+
+// Static B+ tree with nodes of 16 keys compared in one SIMD operation.
+// The last layer holds the sorted keys, padded with i32::MAX. Every node above it has 17
+// children and its j-th key is the smallest key of child j+1.
+// Layers are stored from the root down, layer k spans layer_offsets[k]..layer_offsets[k+1].
+pub struct STree {
+    nodes: Box<[Node]>,
+    layer_offsets: Vec<usize>,
+    count: usize,
+}
+
+// Number of keys in the node less than or equal to `key`
+fn rank_in_node(key: i32, node: &Node) -> usize {
+    const ONE: Simd<i32, B> = i32x16::splat(1);
+    const ZERO: Simd<i32, B> = i32x16::splat(0);
+
+    let is_lower_bound = i32x16::from_array(*node).lanes_le(i32x16::splat(key));
+    is_lower_bound.select(ONE, ZERO).horizontal_sum() as usize
+}
+
+impl StaticIndex for STree {
+    fn build(sorted_keys: &[i32]) -> Result<Self, ()> {
+        if sorted_keys.is_empty() {
+            return Err(())
+        }
+
+        let mut layer: Vec<Node> = sorted_keys.chunks(B).map(|chunk| {
+            let mut node = [i32::MAX; B];
+            node[..chunk.len()].copy_from_slice(chunk);
+            node
+        }).collect();
+        //First leaf of each node, so parents can copy the minimum of each child
+        let mut first_leaf: Vec<usize> = (0..layer.len()).collect();
+
+        let mut layers = vec![];
+        while layer.len() > 1 {
+            let parent_layer_size = (layer.len() + B) / (B + 1);
+            let parent_first_leaf = (0..parent_layer_size).map(|i| first_leaf[i*(B + 1)]).collect();
+            let parent_layer = (0..parent_layer_size).map(|i| {
+                let mut node = [i32::MAX; B];
+                for (j, key) in node.iter_mut().enumerate() {
+                    if let Some(&leaf) = first_leaf.get(i*(B + 1) + j + 1) {
+                        *key = sorted_keys[leaf * B];
+                    }
+                }
+                node
+            }).collect();
+
+            layers.push(layer);
+            layer = parent_layer;
+            first_leaf = parent_first_leaf;
+        }
+        layers.push(layer);
+        layers.reverse();
+
+        let layer_offsets = std::iter::once(0).chain(layers.iter().scan(0, |offset, layer| {
+            *offset += layer.len();
+            Some(*offset)
+        })).collect();
+
+        Ok(STree { nodes: layers.concat().into_boxed_slice(), layer_offsets, count: sorted_keys.len() })
+    }
+
+    fn lower_bound(&self, key: i32) -> Option<i32> {
+        let leaves = &self.nodes[self.layer_offsets[self.layer_offsets.len() - 2]..];
+        self.rank(key).checked_sub(1).map(|position| leaves[position / B][position % B])
+    }
+
+    fn rank(&self, key: i32) -> usize {
+        let number_of_layers = self.layer_offsets.len() - 1;
+        let layer_size = |layer: usize| self.layer_offsets[layer + 1] - self.layer_offsets[layer];
+
+        let mut node_number = 0;
+        for layer in 0..number_of_layers - 1 {
+            let node = &self.nodes[self.layer_offsets[layer] + node_number];
+            //Only the last node of a layer has padding, and i32::MAX counts it as a lower bound
+            node_number = (node_number*(B + 1) + rank_in_node(key, node)).min(layer_size(layer + 1) - 1);
+        }
+
+        let leaf = &self.nodes[self.layer_offsets[number_of_layers - 1] + node_number];
+        //Padding is only a lower bound of i32::MAX, whose real lower bound is the last key
+        (node_number*B + rank_in_node(key, leaf)).min(self.count)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.nodes.len() * std::mem::size_of::<Node>()
+    }
+}
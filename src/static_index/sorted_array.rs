@@ -0,0 +1,27 @@
+use crate::static_index::StaticIndex;
+
+pub struct SortedArray {
+    array: Box<[i32]>,
+}
+
+impl StaticIndex for SortedArray {
+    fn build(sorted_keys: &[i32]) -> Result<Self, ()> {
+        if sorted_keys.is_empty() {
+            return Err(())
+        }
+
+        Ok(SortedArray { array: sorted_keys.into() })
+    }
+
+    fn lower_bound(&self, key: i32) -> Option<i32> {
+        self.rank(key).checked_sub(1).map(|position| self.array[position])
+    }
+
+    fn rank(&self, key: i32) -> usize {
+        self.array.partition_point(|&k| k <= key)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.array.len() * std::mem::size_of::<i32>()
+    }
+}
@@ -0,0 +1,429 @@
+use std::convert::TryInto;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::lsm::Store;
+
+// Bytes of the length and checksum in front of every record
+const HEADER_SIZE: usize = 8;
+
+const PUT: u8 = 0;
+const DELETE: u8 = 1;
+
+// Values written to the log
+pub trait Encode: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    // None if `bytes` isn't exactly one encoded value
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Encode for i32 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(i32::from_le_bytes)
+    }
+}
+
+impl Encode for u64 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(u64::from_le_bytes)
+    }
+}
+
+impl Encode for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+// CRC-32 with the IEEE polynomial, bit by bit since records are small
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+// Payloads of the whole records at the start of `log`, and the length they span.
+// Reading stops at the first record that is cut short or fails its checksum.
+fn read_records(log: &[u8]) -> (Vec<&[u8]>, usize) {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while let Some(header) = log.get(offset..offset + HEADER_SIZE) {
+        let length = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        match log.get(offset + HEADER_SIZE..).and_then(|rest| rest.get(..length)) {
+            Some(payload) if crc32(payload) == checksum => {
+                records.push(payload);
+                offset += HEADER_SIZE + length;
+            }
+            _ => break
+        }
+    }
+    (records, offset)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FsyncPolicy {
+    EveryWrite,
+    // Sync once every n writes, a crash loses at most the n-1 writes since the last sync
+    Grouped(usize),
+    // Leave it to the operating system
+    Never,
+}
+
+// Where the log is kept, a File outside of tests
+pub trait LogFile: Read + Write + Seek {
+    fn set_len(&mut self, length: u64) -> io::Result<()>;
+
+    fn sync_data(&mut self) -> io::Result<()>;
+}
+
+impl LogFile for File {
+    fn set_len(&mut self, length: u64) -> io::Result<()> {
+        File::set_len(self, length)
+    }
+
+    fn sync_data(&mut self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+}
+
+// Append-only log of length prefixed, checksummed records
+pub struct WriteAheadLog<F = File> {
+    file: F,
+    policy: FsyncPolicy,
+    unsynced: usize,
+    //End of the last whole record
+    length: u64,
+    //Set when a failed write couldn't be cut off, appending after it would be lost on recovery
+    broken: bool,
+}
+
+impl WriteAheadLog {
+    // Opens or creates the log and returns the payloads of its whole records
+    pub fn open(path: &Path, policy: FsyncPolicy) -> io::Result<(WriteAheadLog, Vec<Vec<u8>>)> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        WriteAheadLog::from_file(file, policy)
+    }
+}
+
+impl <F: LogFile> WriteAheadLog<F> {
+    // A torn or corrupted tail, e.g. from a crash during a write, is cut off so new records follow the last whole one
+    pub fn from_file(mut file: F, policy: FsyncPolicy) -> io::Result<(WriteAheadLog<F>, Vec<Vec<u8>>)> {
+        let mut log = Vec::new();
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_end(&mut log)?;
+
+        let (records, valid_length) = read_records(&log);
+        let records = records.into_iter().map(|payload| payload.to_vec()).collect();
+        if valid_length < log.len() {
+            file.set_len(valid_length as u64)?;
+            file.sync_data()?;
+        }
+        file.seek(SeekFrom::Start(valid_length as u64))?;
+
+        Ok((WriteAheadLog { file, policy, unsynced: 0, length: valid_length as u64, broken: false }, records))
+    }
+
+    // A write that fails partway is cut off again, so later records still follow the last whole one.
+    // If that fails too, the log refuses every later append.
+    pub fn append(&mut self, payload: &[u8]) -> io::Result<()> {
+        if self.broken {
+            return Err(io::Error::other("Log is broken by an earlier failed write"))
+        }
+
+        let mut record = Vec::with_capacity(HEADER_SIZE + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&crc32(payload).to_le_bytes());
+        record.extend_from_slice(payload);
+        if let Err(error) = self.file.write_all(&record) {
+            let length = self.length;
+            if self.file.set_len(length).and_then(|_| self.file.seek(SeekFrom::Start(length))).is_err() {
+                self.broken = true;
+            }
+            return Err(error)
+        }
+        self.length += record.len() as u64;
+
+        self.unsynced += 1;
+        match self.policy {
+            FsyncPolicy::EveryWrite => self.sync(),
+            FsyncPolicy::Grouped(writes) if self.unsynced >= writes => self.sync(),
+            _ => Ok(())
+        }
+    }
+
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.unsynced = 0;
+        self.file.sync_data()
+    }
+}
+
+// Store whose writes are logged before they reach the write buffer. The runs only live in memory,
+// so the log keeps every write and recovery replays all of it.
+pub struct DurableStore<V> {
+    store: Store<V>,
+    log: WriteAheadLog,
+}
+
+impl <V> DurableStore<V> where V: Encode + Clone + Send + Sync + 'static {
+    pub fn open(path: &Path, policy: FsyncPolicy, buffer_capacity: usize, max_runs: usize) -> io::Result<DurableStore<V>> {
+        let (log, records) = WriteAheadLog::open(path, policy)?;
+        let mut store = Store::new(buffer_capacity, max_runs);
+        for record in records {
+            let key = record.get(1..5).and_then(i32::decode);
+            match (record.first(), key) {
+                (Some(&PUT), Some(key)) => {
+                    let value = V::decode(&record[5..])
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Value can't be decoded"))?;
+                    store.put(key, value)
+                }
+                (Some(&DELETE), Some(key)) => store.delete(key),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown log record"))
+            }
+        }
+
+        Ok(DurableStore { store, log })
+    }
+
+    pub fn get(&self, key: i32) -> Option<&V> {
+        self.store.get(key)
+    }
+
+    pub fn put(&mut self, key: i32, value: V) -> io::Result<()> {
+        let mut payload = vec![PUT];
+        key.encode(&mut payload);
+        value.encode(&mut payload);
+        self.log.append(&payload)?;
+        self.store.put(key, value);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, key: i32) -> io::Result<()> {
+        let mut payload = vec![DELETE];
+        key.encode(&mut payload);
+        self.log.append(&payload)?;
+        self.store.delete(key);
+        Ok(())
+    }
+
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.log.sync()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+    use std::path::PathBuf;
+
+    use crate::wal::{crc32, read_records, DurableStore, FsyncPolicy, LogFile, WriteAheadLog};
+
+    fn log_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("obliviousdb-{}-{}.wal", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn checksum_of_known_input() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn recovery_replays_puts_and_deletes() {
+        let path = log_path("replay");
+        {
+            let mut store = DurableStore::open(&path, FsyncPolicy::Grouped(4), 3, 2).unwrap();
+            for key in 0..10 {
+                store.put(key, format!("value {}", key)).unwrap();
+            }
+            store.put(4, "overwritten".to_string()).unwrap();
+            store.delete(7).unwrap();
+            store.sync().unwrap();
+        }
+
+        let mut store: DurableStore<String> = DurableStore::open(&path, FsyncPolicy::EveryWrite, 3, 2).unwrap();
+        assert_eq!(store.get(3).map(String::as_str), Some("value 3"));
+        assert_eq!(store.get(4).map(String::as_str), Some("overwritten"));
+        assert_eq!(store.get(7), None);
+
+        store.put(20, "after recovery".to_string()).unwrap();
+        let store: DurableStore<String> = DurableStore::open(&path, FsyncPolicy::Never, 3, 2).unwrap();
+        assert_eq!(store.get(20).map(String::as_str), Some("after recovery"));
+        assert_eq!(store.get(9).map(String::as_str), Some("value 9"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    // Writer that fails after a number of bytes, like a disk running out of space in the middle of a record
+    struct FailingFile {
+        file: Cursor<Vec<u8>>,
+        bytes_until_failure: Option<usize>,
+        truncate_fails: bool,
+    }
+
+    impl Read for FailingFile {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            self.file.read(buffer)
+        }
+    }
+
+    impl Write for FailingFile {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            match self.bytes_until_failure {
+                Some(0) => Err(io::Error::other("No space left")),
+                Some(bytes) => {
+                    let written = self.file.write(&buffer[..bytes.min(buffer.len())])?;
+                    self.bytes_until_failure = Some(bytes - written);
+                    Ok(written)
+                }
+                None => self.file.write(buffer)
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for FailingFile {
+        fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+            self.file.seek(position)
+        }
+    }
+
+    impl LogFile for FailingFile {
+        fn set_len(&mut self, length: u64) -> io::Result<()> {
+            if self.truncate_fails {
+                return Err(io::Error::other("Truncate failed"))
+            }
+            self.file.get_mut().truncate(length as usize);
+            Ok(())
+        }
+
+        fn sync_data(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_append_does_not_hide_later_records() {
+        let file = FailingFile { file: Cursor::new(Vec::new()), bytes_until_failure: None, truncate_fails: false };
+        let (mut log, _) = WriteAheadLog::from_file(file, FsyncPolicy::EveryWrite).unwrap();
+        log.append(b"first").unwrap();
+
+        log.file.bytes_until_failure = Some(6);
+        assert!(log.append(b"lost in the middle").is_err());
+        log.file.bytes_until_failure = None;
+        log.append(b"after the failure").unwrap();
+
+        let (_, records) = WriteAheadLog::from_file(log.file, FsyncPolicy::Never).unwrap();
+        assert_eq!(records, vec![b"first".to_vec(), b"after the failure".to_vec()]);
+    }
+
+    #[test]
+    fn log_refuses_appends_after_a_failure_it_cannot_cut_off() {
+        let file = FailingFile { file: Cursor::new(Vec::new()), bytes_until_failure: None, truncate_fails: false };
+        let (mut log, _) = WriteAheadLog::from_file(file, FsyncPolicy::EveryWrite).unwrap();
+        log.append(b"first").unwrap();
+
+        log.file.bytes_until_failure = Some(3);
+        log.file.truncate_fails = true;
+        assert!(log.append(b"torn").is_err());
+        log.file.bytes_until_failure = None;
+        assert!(log.append(b"would be lost").is_err());
+
+        log.file.truncate_fails = false;
+        let (_, records) = WriteAheadLog::from_file(log.file, FsyncPolicy::Never).unwrap();
+        assert_eq!(records, vec![b"first".to_vec()]);
+    }
+
+    // Fault injection: a crash can leave any prefix of the log, or garbage in its last record.
+    // Recovery must keep exactly the whole records before the damage.
+    #[test]
+    fn recovery_keeps_only_whole_records_of_a_damaged_log() {
+        let path = log_path("faults");
+        let mut writes = Vec::new();
+        {
+            let mut store = DurableStore::open(&path, FsyncPolicy::Never, 4, 2).unwrap();
+            for i in 0..12u64 {
+                let key = (i * 7 % 5) as i32;
+                if i % 4 == 3 {
+                    store.delete(key).unwrap();
+                    writes.push((key, None));
+                } else {
+                    store.put(key, i).unwrap();
+                    writes.push((key, Some(i)));
+                }
+            }
+        }
+        let log = fs::read(&path).unwrap();
+        let (records, valid_length) = read_records(&log);
+        assert_eq!((records.len(), valid_length), (writes.len(), log.len()));
+
+        let record_ends: Vec<usize> = records.iter()
+            .scan(0, |end, payload| { *end += 8 + payload.len(); Some(*end) })
+            .collect();
+        let check = |damaged: Vec<u8>, whole_records: usize| {
+            fs::write(&path, &damaged).unwrap();
+            let store: DurableStore<u64> = DurableStore::open(&path, FsyncPolicy::Never, 4, 2).unwrap();
+
+            let mut expected = BTreeMap::new();
+            for &(key, value) in &writes[..whole_records] {
+                match value {
+                    Some(value) => { expected.insert(key, value); }
+                    None => { expected.remove(&key); }
+                }
+            }
+            for key in 0..5 {
+                assert_eq!(store.get(key), expected.get(&key), "Key {} after keeping {} records", key, whole_records);
+            }
+            let length_after_recovery = fs::metadata(&path).unwrap().len() as usize;
+            assert_eq!(length_after_recovery, whole_records.checked_sub(1).map_or(0, |last| record_ends[last]),
+                       "Damaged tail is cut off");
+        };
+
+        for offset in 0..=log.len() {
+            let whole_records = record_ends.iter().filter(|&&end| end <= offset).count();
+            check(log[..offset].to_vec(), whole_records);
+        }
+        for offset in 0..log.len() {
+            let mut corrupted = log.clone();
+            corrupted[offset] ^= 0x5A;
+            let whole_records = record_ends.iter().filter(|&&end| end <= offset).count();
+            check(corrupted, whole_records);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}